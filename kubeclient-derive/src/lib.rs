@@ -0,0 +1,166 @@
+//! Derive macros for [`kubeclient`](https://docs.rs/kubeclient)
+//!
+//! These are re-exported by `kubeclient` when its `derive` feature is enabled.
+
+extern crate proc_macro;
+extern crate proc_macro2;
+#[macro_use] extern crate quote;
+extern crate syn;
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use syn::{Data, DeriveInput, Fields, Ident, Lit, Meta, NestedMeta, Type};
+
+/// Implements `Resource` and `ListableResource` for a custom resource type
+///
+/// The kind is described by a `#[kube(...)]` attribute:
+///
+/// - `group` (required): the API group, e.g. `"stable.example.com"`
+/// - `version` (required): the API version, e.g. `"v1"`
+/// - `kind`: defaults to the name of the type
+/// - `plural`: defaults to the lowercase kind with an `s` appended
+/// - `namespaced`: resources live in a namespace, otherwise they're cluster-wide
+///
+/// The type must have a `metadata: ObjectMeta` field and also derive `Serialize`
+/// and `Deserialize`. A `{Kind}List` type is generated to deserialize responses
+/// when listing resources.
+///
+/// ## Examples
+///
+/// ```ignore
+/// use kubeclient::CustomResource;
+///
+/// #[derive(Serialize, Deserialize, CustomResource)]
+/// #[kube(group = "stable.example.com", version = "v1", namespaced)]
+/// pub struct CronTab {
+///     pub metadata: ObjectMeta,
+///     pub spec: CronTabSpec,
+/// }
+///
+/// let crontabs = kube.resources::<CronTab>().list(None)?;
+/// ```
+#[proc_macro_derive(CustomResource, attributes(kube))]
+pub fn derive_custom_resource(input: TokenStream) -> TokenStream {
+    let input = syn::parse_macro_input!(input as DeriveInput);
+    match custom_resource(&input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+#[derive(Default)]
+struct KubeAttrs {
+    group: Option<String>,
+    version: Option<String>,
+    kind: Option<String>,
+    plural: Option<String>,
+    namespaced: bool,
+}
+
+fn custom_resource(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    if !input.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(&input.generics, "CustomResource can't be derived for generic types"));
+    }
+
+    let metadata_ty = metadata_type(input)?;
+    let attrs = kube_attrs(input)?;
+    let ident = &input.ident;
+    let vis = &input.vis;
+
+    let group = match attrs.group {
+        Some(group) => group,
+        None => return Err(syn::Error::new_spanned(ident, "missing #[kube(group = \"...\")]")),
+    };
+    let version = match attrs.version {
+        Some(version) => version,
+        None => return Err(syn::Error::new_spanned(ident, "missing #[kube(version = \"...\")]")),
+    };
+    let kind = attrs.kind.unwrap_or_else(|| ident.to_string());
+    let plural = attrs.plural.unwrap_or_else(|| format!("{}s", kind.to_lowercase()));
+    let scope = if attrs.namespaced {
+        quote! { ::kubeclient::resources::Scope::Namespaced }
+    } else {
+        quote! { ::kubeclient::resources::Scope::Cluster }
+    };
+
+    let list_ident = Ident::new(&format!("{}List", ident), Span::call_site());
+    let list_doc = format!("A list of `{}` resources", ident);
+
+    Ok(quote! {
+        impl ::kubeclient::resources::Resource for #ident {
+            fn group() -> &'static str { #group }
+            fn version() -> &'static str { #version }
+            fn kind() -> &'static str { #kind }
+            fn plural() -> &'static str { #plural }
+            fn scope() -> ::kubeclient::resources::Scope { #scope }
+            fn metadata(&self) -> &#metadata_ty { &self.metadata }
+        }
+
+        #[doc = #list_doc]
+        #[derive(Deserialize)]
+        #vis struct #list_ident {
+            pub items: Vec<#ident>,
+        }
+
+        impl ::kubeclient::resources::ListableResource for #ident {
+            type ListResponse = #list_ident;
+            fn list_items(response: Self::ListResponse) -> Vec<Self> {
+                response.items
+            }
+        }
+    })
+}
+
+// The type of the `metadata` field, so the generated code doesn't have to
+// name `ObjectMeta` through a crate path the deriving crate may not have
+fn metadata_type(input: &DeriveInput) -> syn::Result<&Type> {
+    if let Data::Struct(ref data) = input.data {
+        if let Fields::Named(ref fields) = data.fields {
+            let metadata = fields.named.iter().find(|field| match field.ident {
+                Some(ref ident) => ident == "metadata",
+                None => false,
+            });
+            if let Some(field) = metadata {
+                return Ok(&field.ty);
+            }
+        }
+    }
+    Err(syn::Error::new_spanned(&input.ident, "CustomResource requires a `metadata: ObjectMeta` field"))
+}
+
+fn kube_attrs(input: &DeriveInput) -> syn::Result<KubeAttrs> {
+    let mut attrs = KubeAttrs::default();
+    for attr in input.attrs.iter().filter(|attr| attr.path.is_ident("kube")) {
+        let list = match attr.parse_meta()? {
+            Meta::List(list) => list,
+            meta => return Err(syn::Error::new_spanned(meta, "expected #[kube(...)]")),
+        };
+        for nested in list.nested {
+            match nested {
+                NestedMeta::Meta(Meta::Path(ref path)) if path.is_ident("namespaced") => {
+                    attrs.namespaced = true;
+                }
+                NestedMeta::Meta(Meta::NameValue(ref pair)) => {
+                    let value = match pair.lit {
+                        Lit::Str(ref value) => value.value(),
+                        ref lit => return Err(syn::Error::new_spanned(lit, "expected a string")),
+                    };
+                    let field = if pair.path.is_ident("group") {
+                        &mut attrs.group
+                    } else if pair.path.is_ident("version") {
+                        &mut attrs.version
+                    } else if pair.path.is_ident("kind") {
+                        &mut attrs.kind
+                    } else if pair.path.is_ident("plural") {
+                        &mut attrs.plural
+                    } else {
+                        return Err(syn::Error::new_spanned(&pair.path, "unknown kube attribute"));
+                    };
+                    *field = Some(value);
+                }
+                other => return Err(syn::Error::new_spanned(other, "unknown kube attribute")),
+            }
+        }
+    }
+    Ok(attrs)
+}
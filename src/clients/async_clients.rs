@@ -0,0 +1,646 @@
+//! Futures-based clients, enabled by the `async` cargo feature
+//!
+//! These mirror the blocking [`Kubernetes`](../struct.Kubernetes.html) client and its
+//! resource clients, but return futures instead of blocking the current thread.
+//! The futures must be run on a tokio runtime.
+//!
+//! Refreshing credentials (e.g. running an exec plugin) still blocks briefly,
+//! as it does for the blocking client.
+//!
+//! The async client covers reading, listing, and writing typed resources. It
+//! doesn't support:
+//!
+//! - a custom [`Transport`](../trait.Transport.html) or [`Middleware`](../trait.Middleware.html),
+//!   including the request logging enabled by
+//!   [`KubernetesBuilder::verbosity`](../struct.KubernetesBuilder.html#method.verbosity),
+//!   so `build_async` fails if any are set
+//! - exec credential plugins that return client certificates
+//! - dynamic clients, discovery, and applying or replacing manifest files
+//!
+//! ## Examples
+//!
+//! ```no_run
+//! # extern crate futures;
+//! # extern crate kubeclient;
+//! # extern crate tokio;
+//! use futures::Future;
+//! use kubeclient::prelude::*;
+//! use kubeclient::AsyncKubernetes;
+//!
+//! let kube = AsyncKubernetes::load_conf("admin.conf")?;
+//! let task = kube.nodes().list(None)
+//!     .map(|nodes| for node in nodes {
+//!         println!("Found node: {}", node.metadata.name.unwrap());
+//!     })
+//!     .map_err(|err| eprintln!("Failed to list nodes: {}", err));
+//! tokio::run(task);
+//! ```
+
+use futures::{future, Future, Stream};
+use futures::future::{Either, Loop};
+use reqwest::{header, Method, StatusCode};
+use reqwest::async::{Client, Response};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json;
+use std::marker::PhantomData;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio_timer::Delay;
+use url::Url;
+use config::{ClusterContext, KubeConfig};
+use resources::*;
+use errors::*;
+use super::auth::{Auth, Impersonation};
+use super::builder::KubernetesBuilder;
+use super::low_level::{content_type, error_from_body, pkcs12_identity, ClientSettings, KindRoute, ResourceRoute};
+use super::rate_limit::RateLimiter;
+use super::retry::RetryPolicy;
+use super::resource_clients::MAX_UPDATE_ATTEMPTS;
+use super::resource_namespace;
+
+// A request body's content type and bytes
+type RequestBody = (&'static str, Vec<u8>);
+
+/// A boxed future resolving to `T`
+pub type KubeFuture<T> = Box<dyn Future<Item = T, Error = Error> + Send>;
+
+// Returns early with a failed future
+macro_rules! try_future {
+    ($e:expr) => {
+        match $e {
+            Ok(v) => v,
+            Err(err) => return Box::new(future::err(Error::from(err))),
+        }
+    };
+}
+
+/// The async counterpart of [`Kubernetes`](../struct.Kubernetes.html)
+#[derive(Clone)]
+pub struct AsyncKubernetes {
+    low_level: AsyncKubeLowLevel,
+    namespace: Option<String>,
+    default_namespace: Option<String>,
+}
+
+impl AsyncKubernetes {
+    /// Initialize a client from a Kubernetes config file
+    ///
+    /// See [`Kubernetes::load_conf`](../struct.Kubernetes.html#method.load_conf).
+    pub fn load_conf<P: AsRef<Path>>(path: P) -> Result<AsyncKubernetes> {
+        let kubeconfig = KubeConfig::load(path)?;
+        AsyncKubernetes::from_context(kubeconfig.default_context()?)
+    }
+
+    /// Initialize a client from a named context in a Kubernetes config file
+    ///
+    /// See [`Kubernetes::load_conf_with_context`](../struct.Kubernetes.html#method.load_conf_with_context).
+    pub fn load_conf_with_context<P: AsRef<Path>>(path: P, context: &str) -> Result<AsyncKubernetes> {
+        let kubeconfig = KubeConfig::load(path)?;
+        AsyncKubernetes::from_context(kubeconfig.context(context)?)
+    }
+
+    /// Initialize a client from the kubeconfig files listed in `$KUBECONFIG`
+    ///
+    /// See [`Kubernetes::from_env`](../struct.Kubernetes.html#method.from_env).
+    pub fn from_env() -> Result<AsyncKubernetes> {
+        let kubeconfig = KubeConfig::load_merged()?;
+        AsyncKubernetes::from_context(kubeconfig.default_context()?)
+    }
+
+    /// Initialize a client from the service account of the pod it is running in
+    ///
+    /// See [`Kubernetes::in_cluster`](../struct.Kubernetes.html#method.in_cluster).
+    pub fn in_cluster() -> Result<AsyncKubernetes> {
+        AsyncKubernetes::from_context(ClusterContext::in_cluster()?)
+    }
+
+    /// Initialize an in-cluster client reading service account credentials from `dir`
+    ///
+    /// See [`Kubernetes::from_service_account`](../struct.Kubernetes.html#method.from_service_account).
+    pub fn from_service_account<P: AsRef<Path>>(dir: P) -> Result<AsyncKubernetes> {
+        AsyncKubernetes::from_context(ClusterContext::from_service_account(dir)?)
+    }
+
+    /// Initialize a client using in-cluster config, falling back to kubeconfig
+    ///
+    /// See [`Kubernetes::infer`](../struct.Kubernetes.html#method.infer).
+    pub fn infer() -> Result<AsyncKubernetes> {
+        match AsyncKubernetes::in_cluster() {
+            Ok(kube) => Ok(kube),
+            Err(in_cluster_err) => AsyncKubernetes::from_env().chain_err(|| {
+                format!("Unable to load in-cluster config ({}) or kubeconfig", in_cluster_err)
+            }),
+        }
+    }
+
+    /// Initialize a client from a kubeconfig context
+    ///
+    /// To set options like the request timeout, use
+    /// [`KubernetesBuilder::build_async`](../struct.KubernetesBuilder.html#method.build_async).
+    pub fn from_context(context: ClusterContext) -> Result<AsyncKubernetes> {
+        KubernetesBuilder::from_context(context).build_async()
+    }
+
+    pub(crate) fn new(settings: ClientSettings, default_namespace: Option<String>) -> Result<AsyncKubernetes> {
+        let low_level = AsyncKubeLowLevel::new(settings)?;
+        Ok(AsyncKubernetes { low_level, namespace: None, default_namespace })
+    }
+
+    /// Get a client for managing `ConfigMaps`
+    pub fn config_maps(&self) -> AsyncKubeClient<ConfigMap> {
+        AsyncKubeClient { kube: self.clone(), _marker: PhantomData }
+    }
+
+    /// Get a client for managing `Deployments`
+    pub fn deployments(&self) -> AsyncKubeClient<Deployment> {
+        AsyncKubeClient { kube: self.clone(), _marker: PhantomData }
+    }
+
+    /// Get a client for managing `NetworkPolicies`
+    pub fn network_policies(&self) -> AsyncKubeClient<NetworkPolicy> {
+        AsyncKubeClient { kube: self.clone(), _marker: PhantomData }
+    }
+
+    /// Get a client for managing `Nodes`
+    pub fn nodes(&self) -> AsyncKubeClient<Node> {
+        AsyncKubeClient { kube: self.clone(), _marker: PhantomData }
+    }
+
+    /// Get a client for managing `Pods`
+    pub fn pods(&self) -> AsyncKubeClient<Pod> {
+        AsyncKubeClient { kube: self.clone(), _marker: PhantomData }
+    }
+
+    /// Get a client for managing `Secrets`
+    pub fn secrets(&self) -> AsyncKubeClient<Secret> {
+        AsyncKubeClient { kube: self.clone(), _marker: PhantomData }
+    }
+
+    /// Get a client for managing `Services`
+    pub fn services(&self) -> AsyncKubeClient<Service> {
+        AsyncKubeClient { kube: self.clone(), _marker: PhantomData }
+    }
+
+    /// Get a client for managing resources of any type `R`, e.g. a custom resource
+    pub fn resources<R: Resource>(&self) -> AsyncKubeClient<R> {
+        AsyncKubeClient { kube: self.clone(), _marker: PhantomData }
+    }
+
+    /// Get a client that uses a specific namespace
+    pub fn namespace(&self, namespace: &str) -> AsyncKubernetes {
+        let mut kube = self.clone();
+        kube.namespace = Some(namespace.to_owned());
+        kube
+    }
+
+    /// Get a client that impersonates another user
+    ///
+    /// See [`Kubernetes::impersonate`](../struct.Kubernetes.html#method.impersonate).
+    pub fn impersonate(&self, user: &str) -> AsyncKubernetes {
+        let mut kube = self.clone();
+        kube.low_level.impersonation.user = Some(user.to_owned());
+        kube.low_level.impersonation.groups.clear();
+        kube.low_level.impersonation.extra.clear();
+        kube
+    }
+
+    /// Get a client that impersonates the given groups
+    pub fn groups(&self, groups: &[&str]) -> AsyncKubernetes {
+        let mut kube = self.clone();
+        kube.low_level.impersonation.groups = groups.iter().map(|&g| g.to_owned()).collect();
+        kube
+    }
+
+    /// Get a client that impersonates an extra user field, e.g. `scopes`
+    ///
+    /// See [`Kubernetes::extra`](../struct.Kubernetes.html#method.extra).
+    pub fn extra(&self, key: &str, values: &[&str]) -> AsyncKubernetes {
+        let mut kube = self.clone();
+        kube.low_level.impersonation.extra.insert(
+            key.to_owned(),
+            values.iter().map(|&v| v.to_owned()).collect(),
+        );
+        kube
+    }
+
+    /// Get a client that retries transient failures according to `policy`
+    pub fn retry_policy(&self, policy: RetryPolicy) -> AsyncKubernetes {
+        let mut kube = self.clone();
+        kube.low_level.retry = policy;
+        kube
+    }
+
+    /// Get a client with its own rate limit
+    ///
+    /// See [`Kubernetes::rate_limit`](../struct.Kubernetes.html#method.rate_limit).
+    pub fn rate_limit(&self, qps: f32, burst: u32) -> AsyncKubernetes {
+        let mut kube = self.clone();
+        kube.low_level.rate_limiter = RateLimiter::new(qps, burst);
+        kube
+    }
+
+    /// Check to see if the Kubernetes API is healthy
+    pub fn healthy(&self) -> KubeFuture<bool> {
+        Box::new(self.low_level.health().map(|output| output == "ok"))
+    }
+
+    /// Creates a resource from a typed resource defintion
+    pub fn create<R>(&self, resource: &R) -> KubeFuture<R>
+    where R: Resource + Send + 'static
+    {
+        let api = R::api();
+        let mut route = KindRoute::new(&api, R::plural());
+        if let Some(ns) = self.get_ns::<R>() {
+            route.namespace(ns);
+        }
+        let url = try_future!(route.build(&self.low_level.base_url));
+        self.low_level.post_json(url, resource)
+    }
+
+    fn exists<R: Resource>(&self, name: &str) -> KubeFuture<bool> {
+        let api = R::api();
+        let mut route = ResourceRoute::new(&api, R::plural(), name);
+        if let Some(ns) = self.get_ns::<R>() {
+            route.namespace(ns);
+        }
+        let url = try_future!(route.build(&self.low_level.base_url));
+        self.low_level.exists(url)
+    }
+
+    fn get<R>(&self, name: &str) -> KubeFuture<R>
+    where R: Resource + Send + 'static
+    {
+        let api = R::api();
+        let mut route = ResourceRoute::new(&api, R::plural(), name);
+        if let Some(ns) = self.get_ns::<R>() {
+            route.namespace(ns);
+        }
+        let url = try_future!(route.build(&self.low_level.base_url));
+        self.low_level.get_json(url)
+    }
+
+    fn list<R>(&self, query: Option<&ListQuery>) -> KubeFuture<Vec<R>>
+    where R: ListableResource + Send + 'static,
+          R::ListResponse: Send + 'static,
+    {
+        let api = R::api();
+        let mut route = KindRoute::new(&api, R::plural());
+        if let Some(ns) = self.get_ns::<R>() {
+            route.namespace(ns);
+        }
+        if let Some(query) = query {
+            route.query(query.as_query_pairs());
+        }
+        let url = try_future!(route.build(&self.low_level.base_url));
+        Box::new(self.low_level.get_json::<R::ListResponse>(url).map(R::list_items))
+    }
+
+    fn patch<R>(&self, name: &str, patch: &Patch) -> KubeFuture<R>
+    where R: Resource + Send + 'static
+    {
+        let api = R::api();
+        let mut route = ResourceRoute::new(&api, R::plural(), name);
+        if let Some(ns) = self.get_ns::<R>() {
+            route.namespace(ns);
+        }
+        let url = try_future!(route.build(&self.low_level.base_url));
+        self.low_level.patch_json(url, patch)
+    }
+
+    // Uses the resource's namespace if set, like `Kubernetes::replace_resource`
+    fn replace_resource<R>(&self, resource: &R) -> KubeFuture<R>
+    where R: Resource + Send + 'static
+    {
+        let metadata = resource.metadata();
+        let name = match metadata.name {
+            Some(ref name) => name,
+            None => return Box::new(future::err("must set metadata.name to replace kubernetes resource".into())),
+        };
+        let api = R::api();
+        let mut route = ResourceRoute::new(&api, R::plural(), name);
+        if let Some(ns) = self.get_ns::<R>() {
+            route.namespace(metadata.namespace.as_ref().map_or(ns, String::as_str));
+        }
+        let url = try_future!(route.build(&self.low_level.base_url));
+        self.low_level.put_json(url, resource)
+    }
+
+    fn delete<R: Resource>(&self, name: &str) -> KubeFuture<()> {
+        let api = R::api();
+        let mut route = ResourceRoute::new(&api, R::plural(), name);
+        if let Some(ns) = self.get_ns::<R>() {
+            route.namespace(ns);
+        }
+        let url = try_future!(route.build(&self.low_level.base_url));
+        self.low_level.delete(url)
+    }
+
+    fn get_ns<R: Resource>(&self) -> Option<&str> {
+        resource_namespace::<R>(self.namespace.as_ref(), self.default_namespace.as_ref())
+    }
+}
+
+/// The async counterpart of [`KubeClient`](../struct.KubeClient.html)
+pub struct AsyncKubeClient<R> {
+    kube: AsyncKubernetes,
+    _marker: PhantomData<R>,
+}
+
+impl<R> AsyncKubeClient<R> {
+    /// Get a client that uses a specific namespace
+    pub fn namespace(&self, namespace: &str) -> Self {
+        AsyncKubeClient { kube: self.kube.namespace(namespace), _marker: PhantomData }
+    }
+}
+
+/// The async counterpart of [`ReadClient`](../trait.ReadClient.html)
+pub trait AsyncReadClient {
+    type R;
+    /// Indicates whether or not the named resource exists in the Kubernetes cluster
+    fn exists(&self, name: &str) -> KubeFuture<bool>;
+
+    /// Gets the named resource
+    fn get(&self, name: &str) -> KubeFuture<Self::R>;
+}
+
+/// The async counterpart of [`WriteClient`](../trait.WriteClient.html)
+pub trait AsyncWriteClient {
+    type R;
+    /// Creates the named resource
+    fn create(&self, resource: &Self::R) -> KubeFuture<Self::R>;
+
+    /// Replaces the resource with the same name
+    ///
+    /// See [`WriteClient::replace`](../trait.WriteClient.html#tymethod.replace).
+    fn replace(&self, resource: &Self::R) -> KubeFuture<Self::R>;
+
+    /// Updates the named resource by getting it, calling `update` on it, then replacing it
+    ///
+    /// See [`WriteClient::update_with`](../trait.WriteClient.html#tymethod.update_with).
+    fn update_with<F>(&self, name: &str, update: F) -> KubeFuture<Self::R>
+    where F: FnMut(&mut Self::R) + Send + 'static;
+
+    /// Patches the named resource, returning the updated resource
+    fn patch(&self, name: &str, patch: Patch) -> KubeFuture<Self::R>;
+
+    /// Deletes the named resource
+    fn delete(&self, name: &str) -> KubeFuture<()>;
+}
+
+/// The async counterpart of [`ListClient`](../trait.ListClient.html)
+pub trait AsyncListClient {
+    type R;
+    /// Lists resources of a particular type
+    fn list(&self, query: Option<&ListQuery>) -> KubeFuture<Vec<Self::R>>;
+}
+
+impl<R: Resource + Send + 'static> AsyncReadClient for AsyncKubeClient<R> {
+    type R = R;
+
+    fn exists(&self, name: &str) -> KubeFuture<bool> {
+        self.kube.exists::<Self::R>(name)
+    }
+    fn get(&self, name: &str) -> KubeFuture<Self::R> {
+        self.kube.get::<Self::R>(name)
+    }
+}
+
+impl<R> AsyncListClient for AsyncKubeClient<R>
+where R: ListableResource + Send + 'static,
+      R::ListResponse: Send + 'static,
+{
+    type R = R;
+
+    fn list(&self, query: Option<&ListQuery>) -> KubeFuture<Vec<Self::R>> {
+        self.kube.list::<Self::R>(query)
+    }
+}
+
+impl<R: Resource + Send + 'static> AsyncWriteClient for AsyncKubeClient<R> {
+    type R = R;
+
+    fn create(&self, resource: &Self::R) -> KubeFuture<Self::R> {
+        self.kube.create(resource)
+    }
+
+    fn replace(&self, resource: &Self::R) -> KubeFuture<Self::R> {
+        self.kube.replace_resource(resource)
+    }
+
+    fn update_with<F>(&self, name: &str, update: F) -> KubeFuture<Self::R>
+    where F: FnMut(&mut Self::R) + Send + 'static
+    {
+        let (kube, name) = (self.kube.clone(), name.to_owned());
+        let update = Arc::new(Mutex::new(update));
+        Box::new(future::loop_fn(1, move |attempt| {
+            let (replacer, update) = (kube.clone(), update.clone());
+            kube.get::<Self::R>(&name)
+                .and_then(move |mut resource| {
+                    let mut update = update.lock().unwrap_or_else(|err| err.into_inner());
+                    (*update)(&mut resource);
+                    replacer.replace_resource(&resource)
+                })
+                .then(move |result| match result {
+                    Err(ref err) if err.is_conflict() && attempt < MAX_UPDATE_ATTEMPTS => Ok(Loop::Continue(attempt + 1)),
+                    result => result.map(Loop::Break),
+                })
+        }))
+    }
+
+    fn patch(&self, name: &str, patch: Patch) -> KubeFuture<Self::R> {
+        self.kube.patch(name, &patch)
+    }
+
+    fn delete(&self, name: &str) -> KubeFuture<()> {
+        self.kube.delete::<Self::R>(name)
+    }
+}
+
+#[derive(Clone)]
+struct AsyncKubeLowLevel {
+    client: Client,
+    base_url: Url,
+    auth: Auth,
+    impersonation: Impersonation,
+    retry: RetryPolicy,
+    rate_limiter: RateLimiter,
+}
+
+impl AsyncKubeLowLevel {
+    fn new(settings: ClientSettings) -> Result<AsyncKubeLowLevel> {
+        let mut client = Client::builder()
+            .danger_accept_invalid_certs(settings.accept_invalid_certs)
+            .default_headers(settings.headers);
+        if let Some(root_cert) = settings.root_cert {
+            client = client.add_root_certificate(root_cert);
+        }
+        // Unlike the blocking client, this client isn't rebuilt when a
+        // credential plugin returns a new certificate, so those aren't supported
+        if settings.client_identity.is_none() && settings.auth.client_identity()?.is_some() {
+            bail!("Exec credential plugins that return client certificates aren't supported by the async client");
+        }
+        if let Some((crt, key)) = settings.client_identity {
+            client = client.identity(pkcs12_identity(&crt, &key)?);
+        }
+        if let Some(proxy) = settings.proxy {
+            client = client.proxy(proxy);
+        }
+        if let Some(timeout) = settings.timeout {
+            client = client.timeout(timeout);
+        }
+        let client = client.build().chain_err(|| "Failed to build reqwest client")?;
+
+        Ok(AsyncKubeLowLevel {
+            client,
+            base_url: settings.base_url,
+            auth: settings.auth,
+            impersonation: settings.impersonation,
+            retry: settings.retry,
+            rate_limiter: settings.rate_limiter,
+        })
+    }
+
+    fn health(&self) -> KubeFuture<String> {
+        let url = try_future!(self.base_url.join("healthz"));
+        let output = self.http_get(url)
+            .and_then(|response| response.into_body().concat2().map_err(Error::from))
+            .map(|body| String::from_utf8_lossy(&body).into_owned());
+        Box::new(output)
+    }
+
+    fn exists(&self, url: Url) -> KubeFuture<bool> {
+        Box::new(self.send(Method::GET, url, None).and_then(|response| {
+            match response.status() {
+                StatusCode::NOT_FOUND => Either::A(future::ok(false)),
+                s if s.is_success() => Either::A(future::ok(true)),
+                _ => Either::B(api_error(response)),
+            }
+        }))
+    }
+
+    fn get_json<D>(&self, url: Url) -> KubeFuture<D>
+    where D: DeserializeOwned + Send + 'static
+    {
+        self.request_json(Method::GET, url, None)
+    }
+
+    fn post_json<S, D>(&self, url: Url, body: &S) -> KubeFuture<D>
+    where S: Serialize,
+          D: DeserializeOwned + Send + 'static,
+    {
+        let body = try_future!(serde_json::to_vec(body));
+        self.request_json(Method::POST, url, Some(("application/json", body)))
+    }
+
+    fn put_json<S, D>(&self, url: Url, body: &S) -> KubeFuture<D>
+    where S: Serialize,
+          D: DeserializeOwned + Send + 'static,
+    {
+        let body = try_future!(serde_json::to_vec(body));
+        self.request_json(Method::PUT, url, Some(("application/json", body)))
+    }
+
+    fn patch_json<D>(&self, url: Url, patch: &Patch) -> KubeFuture<D>
+    where D: DeserializeOwned + Send + 'static
+    {
+        let body = try_future!(serde_json::to_vec(patch.body()));
+        self.request_json(Method::PATCH, url, Some((patch.content_type(), body)))
+    }
+
+    fn delete(&self, url: Url) -> KubeFuture<()> {
+        Box::new(self.send(Method::DELETE, url, None).and_then(|response| {
+            if !response.status().is_success() {
+                return api_error(response);
+            }
+            Box::new(future::ok(()))
+        }))
+    }
+
+    fn http_get(&self, url: Url) -> KubeFuture<Response> {
+        Box::new(self.send(Method::GET, url, None).and_then(|response| {
+            if !response.status().is_success() {
+                return api_error(response);
+            }
+            Box::new(future::ok(response))
+        }))
+    }
+
+    fn request_json<D>(&self, method: Method, url: Url, body: Option<RequestBody>) -> KubeFuture<D>
+    where D: DeserializeOwned + Send + 'static
+    {
+        Box::new(self.send(method, url, body).and_then(|mut response| {
+            if !response.status().is_success() {
+                return Either::A(api_error(response));
+            }
+            Either::B(response.json().map_err(|err| Error::with_chain(err, "Failed to decode JSON response")))
+        }))
+    }
+
+    /// Sends a request with the client's credentials
+    ///
+    /// Transient failures are retried according to the client's `RetryPolicy`.
+    fn send(&self, method: Method, url: Url, body: Option<RequestBody>) -> KubeFuture<Response> {
+        let this = self.clone();
+        Box::new(future::loop_fn(1, move |attempt| {
+            let (this, method) = (this.clone(), method.clone());
+            this.send_authenticated(method.clone(), url.clone(), body.clone()).then(move |result| {
+                let delay = {
+                    let outcome = result.as_ref().map(|r| (r.status(), r.headers()));
+                    this.retry.retry_delay(&method, outcome, attempt)
+                };
+                match delay {
+                    Some(delay) => Either::A(sleep(delay).map(move |_| Loop::Continue(attempt + 1))),
+                    None => Either::B(future::result(
+                        result.map(Loop::Break).chain_err(|| format!("Failed to {} URL", method))
+                    )),
+                }
+            })
+        }))
+    }
+
+    /// Sends a request, retrying once with fresh credentials
+    /// if the API server rejects credentials that can be refreshed
+    fn send_authenticated(&self, method: Method, url: Url, body: Option<RequestBody>) -> KubeFuture<Response> {
+        let this = self.clone();
+        Box::new(self.send_once(method.clone(), url.clone(), body.clone()).and_then(move |response| {
+            if response.status() == StatusCode::UNAUTHORIZED && this.auth.invalidate() {
+                return this.send_once(method, url, body);
+            }
+            Box::new(future::ok(response))
+        }))
+    }
+
+    fn send_once(&self, method: Method, url: Url, body: Option<RequestBody>) -> KubeFuture<Response> {
+        let mut headers = header::HeaderMap::new();
+        try_future!(self.auth.apply(&mut headers));
+        try_future!(self.impersonation.apply(&mut headers));
+
+        let mut req = self.client.request(method, url).headers(headers);
+        if let Some((content_type, bytes)) = body {
+            req = req.header(header::CONTENT_TYPE, content_type).body(bytes);
+        }
+
+        let send = move |_| req.send().map_err(Error::from);
+        match self.rate_limiter.reserve() {
+            Some(wait) => Box::new(sleep(wait).and_then(send)),
+            None => Box::new(send(())),
+        }
+    }
+}
+
+/// Converts an unsuccessful response into an error
+fn api_error<T: Send + 'static>(response: Response) -> KubeFuture<T> {
+    let code = response.status();
+    let content_type = content_type(response.headers());
+    Box::new(response.into_body().concat2().then(move |body| match body {
+        Ok(body) => Err(error_from_body(code, content_type, &body)),
+        Err(err) => Err(Error::with_chain(err, format!("Failed to read error response ({})", code))),
+    }))
+}
+
+fn sleep(duration: Duration) -> impl Future<Item = (), Error = Error> {
+    Delay::new(Instant::now() + duration)
+        .map_err(|err| Error::with_chain(err, "Failed to wait for timer"))
+}
@@ -0,0 +1,513 @@
+use reqwest::{self, header::{HeaderMap, HeaderName, HeaderValue}};
+use headers_ext::{self, HeaderMapExt};
+use base64;
+use chrono::{DateTime, Utc};
+use config::{self, AuthInfo, ClusterContext, ExecConfig};
+use openssl::x509::X509;
+use openssl::pkey::{PKey, Private};
+use serde_json::{self, Value};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use url::percent_encoding::{utf8_percent_encode, PATH_SEGMENT_ENCODE_SET};
+use errors::*;
+
+const EXEC_INFO_ENV: &str = "KUBERNETES_EXEC_INFO";
+
+/// How long a token read from a `tokenFile` is used before reading the file again
+const TOKEN_FILE_REFRESH: Duration = Duration::from_secs(60);
+
+/// The credentials used to authenticate each request
+///
+/// Static credentials are sent as-is, while credentials from plugins
+/// are cached and refreshed when they expire or the API server rejects them.
+#[derive(Clone)]
+pub(crate) enum Auth {
+    None,
+    Basic { username: String, password: String },
+    Bearer(String),
+    TokenFile(Arc<Mutex<TokenFileAuth>>),
+    Exec(Arc<Mutex<ExecAuth>>),
+    Oidc(Arc<Mutex<OidcAuth>>),
+}
+
+impl Auth {
+    pub(crate) fn from_context(context: &ClusterContext) -> Result<Auth> {
+        let auth_info = &context.user;
+        if let (Some(username), Some(password)) = (auth_info.username.clone(), auth_info.password.clone()) {
+            Ok(Auth::Basic { username, password })
+        } else if let Some(path) = auth_info.token_file_path() {
+            let token_file = TokenFileAuth::new(path)
+                .chain_err(|| format!("Invalid tokenFile for user {}", context.user_name))?;
+            Ok(Auth::TokenFile(Arc::new(Mutex::new(token_file))))
+        } else if let Some(ref token) = auth_info.token {
+            Ok(Auth::Bearer(token.to_owned()))
+        } else if let Some(ref exec) = auth_info.exec {
+            let mut exec = exec.clone();
+            if let Some(command) = auth_info.exec_command_path() {
+                exec.command = command.to_string_lossy().into_owned();
+            }
+            let exec = ExecAuth::new(exec)
+                .chain_err(|| format!("Invalid exec credentials for user {}", context.user_name))?;
+            Ok(Auth::Exec(Arc::new(Mutex::new(exec))))
+        } else if let Some(ref provider) = auth_info.auth_provider {
+            match &*provider.name {
+                "oidc" => {
+                    let oidc = OidcAuth {
+                        config: provider.config.clone(),
+                        user_name: context.user_name.clone(),
+                        config_paths: context.config_paths.clone(),
+                        base_dir: auth_info.base_dir(),
+                        stale: false,
+                    };
+                    Ok(Auth::Oidc(Arc::new(Mutex::new(oidc))))
+                }
+                name => bail!("Unsupported auth-provider '{}' for user {}", name, context.user_name),
+            }
+        } else {
+            Ok(Auth::None)
+        }
+    }
+
+    /// Adds the `Authorization` header for a request, refreshing the credentials if needed
+    pub(crate) fn apply(&self, headers: &mut HeaderMap) -> Result<()> {
+        match *self {
+            Auth::None => (),
+            Auth::Basic { ref username, ref password } => {
+                headers.typed_insert(headers_ext::Authorization::basic(username, password));
+            }
+            Auth::Bearer(ref token) => insert_bearer(headers, token)?,
+            Auth::TokenFile(ref token_file) => {
+                let token = token_file.lock().unwrap().token()?;
+                insert_bearer(headers, &token)?;
+            }
+            Auth::Exec(ref exec) => {
+                if let Some(token) = exec.lock().unwrap().token()? {
+                    insert_bearer(headers, &token)?;
+                }
+            }
+            Auth::Oidc(ref oidc) => {
+                if let Some(token) = oidc.lock().unwrap().token()? {
+                    insert_bearer(headers, &token)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Discards any cached credentials after the API server rejected them
+    ///
+    /// Returns true if the credentials will be refreshed for the next request,
+    /// meaning that it is worth retrying the rejected request.
+    pub(crate) fn invalidate(&self) -> bool {
+        match *self {
+            Auth::TokenFile(ref token_file) => {
+                token_file.lock().unwrap().read_at = None;
+                true
+            }
+            Auth::Exec(ref exec) => {
+                exec.lock().unwrap().credential = None;
+                true
+            }
+            Auth::Oidc(ref oidc) => {
+                let mut oidc = oidc.lock().unwrap();
+                oidc.stale = true;
+                oidc.config.contains_key("refresh-token")
+            }
+            _ => false,
+        }
+    }
+
+    /// The client certificate and key provided by a credential plugin, if any
+    pub(crate) fn client_identity(&self) -> Result<Option<(X509, PKey<Private>)>> {
+        match *self {
+            Auth::Exec(ref exec) => exec.lock().unwrap().client_identity(),
+            _ => Ok(None),
+        }
+    }
+
+    /// Changes each time a credential plugin is re-run, and with it
+    /// the client certificate that `client_identity` returns
+    pub(crate) fn generation(&self) -> u64 {
+        match *self {
+            Auth::Exec(ref exec) => exec.lock().unwrap().generation,
+            _ => 0,
+        }
+    }
+}
+
+fn insert_bearer(headers: &mut HeaderMap, token: &str) -> Result<()> {
+    headers.typed_insert(headers_ext::Authorization::bearer(token)
+                         .map_err(|_| Error::from("Invalid bearer token"))?);
+    Ok(())
+}
+
+/// The user, groups, and extra fields to impersonate on each request
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Impersonation {
+    pub(crate) user: Option<String>,
+    pub(crate) groups: Vec<String>,
+    pub(crate) extra: BTreeMap<String, Vec<String>>,
+}
+
+impl Impersonation {
+    pub(crate) fn from_auth_info(auth_info: &AuthInfo) -> Impersonation {
+        Impersonation {
+            user: auth_info.impersonate.clone(),
+            groups: auth_info.impersonate_groups.clone().unwrap_or_default(),
+            extra: auth_info.impersonate_extra.clone().unwrap_or_default(),
+        }
+    }
+
+    /// Adds the `Impersonate-*` headers for a request
+    pub(crate) fn apply(&self, headers: &mut HeaderMap) -> Result<()> {
+        let user = match self.user {
+            Some(ref user) => user,
+            // The API server rejects impersonating groups or extras without a user
+            None => return Ok(()),
+        };
+        headers.insert("impersonate-user", header_value(user)?);
+        for group in &self.groups {
+            headers.append("impersonate-group", header_value(group)?);
+        }
+        for (key, values) in &self.extra {
+            let name = format!("impersonate-extra-{}", utf8_percent_encode(key, PATH_SEGMENT_ENCODE_SET));
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .chain_err(|| format!("Invalid impersonation extra key '{}'", key))?;
+            for value in values {
+                headers.append(name.clone(), header_value(value)?);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn header_value(value: &str) -> Result<HeaderValue> {
+    HeaderValue::from_str(value).chain_err(|| format!("Invalid header value '{}'", value))
+}
+
+/// Reads a bearer token from a file, periodically re-reading it to pick up rotated tokens
+pub(crate) struct TokenFileAuth {
+    path: PathBuf,
+    token: String,
+    read_at: Option<Instant>,
+}
+
+impl TokenFileAuth {
+    fn new(path: PathBuf) -> Result<TokenFileAuth> {
+        let mut token_file = TokenFileAuth { path, token: String::new(), read_at: None };
+        token_file.refresh()?;
+        Ok(token_file)
+    }
+
+    fn token(&mut self) -> Result<String> {
+        let stale = match self.read_at {
+            Some(read_at) => read_at.elapsed() >= TOKEN_FILE_REFRESH,
+            None => true,
+        };
+        if stale {
+            self.refresh()?;
+        }
+        Ok(self.token.clone())
+    }
+
+    fn refresh(&mut self) -> Result<()> {
+        let mut token = String::new();
+        File::open(&self.path)
+            .and_then(|mut f| f.read_to_string(&mut token))
+            .chain_err(|| format!("Unable to read token file {}", self.path.display()))?;
+        self.token = token.trim().to_owned();
+        self.read_at = Some(Instant::now());
+        Ok(())
+    }
+}
+
+/// Runs an exec credential plugin and caches the result until it expires
+pub(crate) struct ExecAuth {
+    config: ExecConfig,
+    credential: Option<ExecCredentialStatus>,
+    generation: u64,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct ExecCredential {
+    kind: Option<String>,
+    status: Option<ExecCredentialStatus>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct ExecCredentialStatus {
+    expiration_timestamp: Option<DateTime<Utc>>,
+    token: Option<String>,
+    client_certificate_data: Option<String>,
+    client_key_data: Option<String>,
+}
+
+impl ExecAuth {
+    fn new(config: ExecConfig) -> Result<ExecAuth> {
+        let mut exec = ExecAuth { config, credential: None, generation: 0 };
+        exec.refresh()?;
+        Ok(exec)
+    }
+
+    fn token(&mut self) -> Result<Option<String>> {
+        Ok(self.credential()?.token.clone())
+    }
+
+    fn client_identity(&mut self) -> Result<Option<(X509, PKey<Private>)>> {
+        let status = self.credential()?;
+        match (status.client_certificate_data.as_ref(), status.client_key_data.as_ref()) {
+            (Some(crt), Some(key)) => {
+                let crt = X509::from_pem(crt.as_bytes())
+                    .chain_err(|| "Exec credential client certificate is not PEM-encoded")?;
+                let key = PKey::private_key_from_pem(key.as_bytes())
+                    .chain_err(|| "Exec credential client key is not PEM-encoded")?;
+                Ok(Some((crt, key)))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn credential(&mut self) -> Result<&ExecCredentialStatus> {
+        let expired = match self.credential {
+            Some(ExecCredentialStatus { expiration_timestamp: Some(exp), .. }) => exp <= Utc::now(),
+            Some(_) => false,
+            None => true,
+        };
+        if expired {
+            self.refresh()?;
+        }
+        Ok(self.credential.as_ref().expect("kubeclient bug: exec credential missing after refresh"))
+    }
+
+    fn refresh(&mut self) -> Result<()> {
+        let api_version = self.config.api_version.clone()
+            .unwrap_or_else(|| "client.authentication.k8s.io/v1beta1".to_owned());
+        let exec_info = json!({
+            "apiVersion": api_version,
+            "kind": "ExecCredential",
+            "spec": { "interactive": false },
+        });
+
+        let mut cmd = Command::new(&self.config.command);
+        if let Some(ref args) = self.config.args {
+            cmd.args(args);
+        }
+        if let Some(ref env) = self.config.env {
+            cmd.envs(env.iter().map(|e| (&e.name, &e.value)));
+        }
+        let output = cmd.env(EXEC_INFO_ENV, exec_info.to_string())
+            .stdin(Stdio::inherit())
+            .stderr(Stdio::inherit())
+            .output()
+            .chain_err(|| format!("Failed to run exec credential plugin '{}'", self.config.command))?;
+
+        if !output.status.success() {
+            bail!("Exec credential plugin '{}' failed: {}", self.config.command, output.status);
+        }
+
+        let credential: ExecCredential = serde_json::from_slice(&output.stdout)
+            .chain_err(|| format!("Failed to decode ExecCredential from '{}'", self.config.command))?;
+        match credential.kind {
+            Some(ref kind) if kind != "ExecCredential" => {
+                bail!("Exec credential plugin '{}' returned unexpected kind", self.config.command);
+            }
+            _ => (),
+        }
+        match credential.status {
+            Some(status) => self.credential = Some(status),
+            None => bail!("Exec credential plugin '{}' did not return a status", self.config.command),
+        }
+        self.generation += 1;
+        Ok(())
+    }
+}
+
+/// Uses an OIDC `id-token` as a bearer token, refreshing it when it expires
+pub(crate) struct OidcAuth {
+    config: BTreeMap<String, String>,
+    user_name: String,
+    config_paths: Vec<PathBuf>,
+    /// For resolving a relative `idp-certificate-authority`
+    base_dir: Option<PathBuf>,
+    stale: bool,
+}
+
+#[derive(Deserialize, Debug)]
+struct OidcDiscovery {
+    token_endpoint: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct OidcTokenResponse {
+    id_token: String,
+    refresh_token: Option<String>,
+}
+
+impl OidcAuth {
+    fn token(&mut self) -> Result<Option<String>> {
+        let expired = match self.config.get("id-token") {
+            Some(id_token) => self.stale || jwt_expired(id_token),
+            None => true,
+        };
+        if expired && self.config.contains_key("refresh-token") {
+            self.refresh()?;
+        }
+        Ok(self.config.get("id-token").cloned())
+    }
+
+    fn refresh(&mut self) -> Result<()> {
+        let (token_endpoint, params) = {
+            let get = |key: &str| self.config.get(key).ok_or_else(|| {
+                Error::from(format!("OIDC auth-provider for user {} is missing '{}'", self.user_name, key))
+            });
+            let issuer = get("idp-issuer-url")?;
+            let mut params = vec![
+                ("grant_type", "refresh_token".to_owned()),
+                ("refresh_token", get("refresh-token")?.to_owned()),
+                ("client_id", get("client-id")?.to_owned()),
+            ];
+            if let Some(secret) = self.config.get("client-secret") {
+                params.push(("client_secret", secret.to_owned()));
+            }
+
+            let discovery_url = format!("{}/.well-known/openid-configuration", issuer.trim_end_matches('/'));
+            let mut response = self.http_client()?.get(&discovery_url)
+                .send()
+                .chain_err(|| "Failed to GET OIDC discovery document")?;
+            if !response.status().is_success() {
+                bail!("OIDC discovery at {} failed: {}", discovery_url, response.status());
+            }
+            let discovery: OidcDiscovery = response.json()
+                .chain_err(|| "Failed to decode OIDC discovery document")?;
+            (discovery.token_endpoint, params)
+        };
+
+        let mut response = self.http_client()?.post(&token_endpoint)
+            .form(&params)
+            .send()
+            .chain_err(|| "Failed to POST OIDC token refresh")?;
+        if !response.status().is_success() {
+            bail!("OIDC token refresh at {} failed: {}", token_endpoint, response.status());
+        }
+        let tokens: OidcTokenResponse = response.json()
+            .chain_err(|| "Failed to decode OIDC token response")?;
+
+        let mut updates = BTreeMap::new();
+        updates.insert("id-token".to_owned(), tokens.id_token);
+        if let Some(refresh_token) = tokens.refresh_token {
+            updates.insert("refresh-token".to_owned(), refresh_token);
+        }
+        self.config.extend(updates.clone());
+        self.stale = false;
+
+        // The refreshed tokens work without being saved, e.g. if the kubeconfig is read-only
+        if let Err(err) = config::persist_auth_provider_config(&self.config_paths, &self.user_name, &updates) {
+            warn!("Failed to persist refreshed OIDC tokens for user {}: {}", self.user_name, err);
+        }
+        Ok(())
+    }
+
+    fn http_client(&self) -> Result<reqwest::Client> {
+        let client = reqwest::Client::builder();
+        let ca = config::get_from_b64data_or_file(
+            &self.config.get("idp-certificate-authority-data").cloned(),
+            self.config.get("idp-certificate-authority").map(|f| config::resolve_path(&self.base_dir, f)),
+            "idp-certificate-authority",
+        )?;
+        let client = match ca {
+            Some(ca) => {
+                let ca = reqwest::Certificate::from_pem(ca.as_bytes())
+                    .chain_err(|| "Invalid OIDC idp-certificate-authority")?;
+                client.add_root_certificate(ca)
+            }
+            None => client,
+        };
+        client.build().chain_err(|| "Failed to build reqwest client")
+    }
+}
+
+/// Checks the `exp` claim of a JWT
+///
+/// Tokens that can't be decoded are treated as unexpired,
+/// leaving it to the API server to accept or reject them.
+fn jwt_expired(jwt: &str) -> bool {
+    let exp = jwt.split('.').nth(1)
+        .and_then(|payload| base64::decode_config(payload, base64::URL_SAFE_NO_PAD).ok())
+        .and_then(|payload| serde_json::from_slice::<Value>(&payload).ok())
+        .and_then(|claims| claims.get("exp").and_then(Value::as_i64));
+    match exp {
+        Some(exp) => exp <= Utc::now().timestamp(),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+    use std::io::Write;
+    use std::net::TcpListener;
+    use std::thread;
+
+    /// Serves OIDC discovery and a token endpoint that hands out `new-id-token`
+    fn stand_in_issuer() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let issuer = format!("http://{}", listener.local_addr().unwrap());
+        let token_endpoint = format!("{}/token", issuer);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut request = [0; 4096];
+                let len = stream.read(&mut request).unwrap();
+                let request = String::from_utf8_lossy(&request[..len]);
+                let body = if request.starts_with("GET /.well-known/openid-configuration") {
+                    json!({ "token_endpoint": token_endpoint })
+                } else {
+                    json!({ "id_token": "new-id-token", "refresh_token": "new-refresh-token" })
+                };
+                let body = body.to_string();
+                write!(stream, "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", body.len(), body).unwrap();
+            }
+        });
+        issuer
+    }
+
+    fn oidc_auth(issuer: &str, config_paths: Vec<PathBuf>) -> OidcAuth {
+        let mut config = BTreeMap::new();
+        config.insert("idp-issuer-url".to_owned(), issuer.to_owned());
+        config.insert("client-id".to_owned(), "kubectl".to_owned());
+        config.insert("id-token".to_owned(), "old-id-token".to_owned());
+        config.insert("refresh-token".to_owned(), "old-refresh-token".to_owned());
+        OidcAuth { config, user_name: "oidc-user".to_owned(), config_paths, base_dir: None, stale: true }
+    }
+
+    #[test]
+    fn oidc_refresh_persists_tokens() {
+        let path = env::temp_dir().join(format!("kubeclient-oidc-{}.yaml", ::std::process::id()));
+        fs::write(&path, "users:\n- name: oidc-user\n  user:\n    auth-provider:\n      name: oidc\n      config:\n        id-token: old-id-token\n").unwrap();
+
+        let mut oidc = oidc_auth(&stand_in_issuer(), vec![path.clone()]);
+        assert_eq!(oidc.token().unwrap().unwrap(), "new-id-token");
+
+        let saved = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert!(saved.contains("id-token: new-id-token"), "{}", saved);
+        assert!(saved.contains("refresh-token: new-refresh-token"), "{}", saved);
+    }
+
+    #[test]
+    fn oidc_refresh_succeeds_when_tokens_cant_be_persisted() {
+        let path = PathBuf::from("/nonexistent/kubeconfig");
+        let mut oidc = oidc_auth(&stand_in_issuer(), vec![path]);
+        assert_eq!(oidc.token().unwrap().unwrap(), "new-id-token");
+        assert_eq!(oidc.config["refresh-token"], "new-refresh-token");
+    }
+}
@@ -0,0 +1,210 @@
+use super::{Kubernetes, LoggingMiddleware, Middleware, RetryPolicy, Transport};
+use super::low_level::{ClientOptions, KubeLowLevel, ProxyOverride};
+#[cfg(feature = "async")]
+use super::async_clients::AsyncKubernetes;
+#[cfg(feature = "async")]
+use super::low_level::ClientSettings;
+use super::rate_limit::RateLimiter;
+use config::{AuthInfo, Cluster, ClusterContext};
+use base64;
+use url::Url;
+use std::sync::Arc;
+use std::time::Duration;
+use errors::*;
+
+/// Builder for configuring a `Kubernetes` client without a kubeconfig file
+///
+/// ## Examples
+///
+/// ```no_run
+/// # use kubeclient::KubernetesBuilder;
+/// # let ca_pem = Vec::new();
+/// let kube = KubernetesBuilder::new("https://10.0.0.1:6443")?
+///     .ca_cert(&ca_pem)
+///     .bearer_token("abc123")
+///     .namespace("my-app")
+///     .build()?;
+/// ```
+pub struct KubernetesBuilder {
+    context: ClusterContext,
+    options: ClientOptions,
+}
+
+impl KubernetesBuilder {
+    /// Start building a client for the API server at `server`
+    pub fn new(server: &str) -> Result<KubernetesBuilder> {
+        let server = Url::parse(server)?;
+        let context = ClusterContext {
+            name: "builder".to_owned(),
+            cluster_name: server.to_string(),
+            cluster: Cluster::new(server),
+            user_name: "builder".to_owned(),
+            user: AuthInfo::default(),
+            namespace: None,
+            extensions: None,
+            config_paths: Vec::new(),
+        };
+        Ok(KubernetesBuilder::from_context(context))
+    }
+
+    /// Start building a client from a kubeconfig context
+    ///
+    /// This allows setting options like the request timeout that
+    /// can't be expressed in a kubeconfig.
+    pub fn from_context(context: ClusterContext) -> KubernetesBuilder {
+        KubernetesBuilder { context, options: ClientOptions::default() }
+    }
+
+    /// Trust a PEM-encoded CA certificate for verifying the API server
+    ///
+    /// If no CA certificate is set, the system roots are used.
+    pub fn ca_cert(mut self, pem: &[u8]) -> KubernetesBuilder {
+        self.context.cluster.ca_file = None;
+        self.context.cluster.ca_data = Some(base64::encode(pem));
+        self
+    }
+
+    /// Skip verifying the API server's certificate
+    pub fn insecure_skip_tls_verify(mut self) -> KubernetesBuilder {
+        self.context.cluster.insecure_tls = Some(true);
+        self
+    }
+
+    /// Authenticate with a PEM-encoded client certificate and private key
+    pub fn client_identity(mut self, cert_pem: &[u8], key_pem: &[u8]) -> KubernetesBuilder {
+        let user = &mut self.context.user;
+        user.client_certificate_file = None;
+        user.client_certificate_data = Some(base64::encode(cert_pem));
+        user.client_key_file = None;
+        user.client_key_data = Some(base64::encode(key_pem));
+        self
+    }
+
+    /// Authenticate with a bearer token
+    pub fn bearer_token(mut self, token: &str) -> KubernetesBuilder {
+        self.context.user.token = Some(token.to_owned());
+        self
+    }
+
+    /// Set the default namespace for namespaced resources
+    pub fn namespace(mut self, namespace: &str) -> KubernetesBuilder {
+        self.context.namespace = Some(namespace.to_owned());
+        self
+    }
+
+    /// Set the timeout for each request
+    pub fn timeout(mut self, timeout: Duration) -> KubernetesBuilder {
+        self.options.timeout = Some(timeout);
+        self
+    }
+
+    /// Set the `User-Agent` sent with each request
+    pub fn user_agent(mut self, user_agent: &str) -> KubernetesBuilder {
+        self.options.user_agent = Some(user_agent.to_owned());
+        self
+    }
+
+    /// Add a header to send with each request
+    ///
+    /// Invalid header names or values are reported by `build`.
+    pub fn header(mut self, name: &str, value: &str) -> KubernetesBuilder {
+        self.options.headers.push((name.to_owned(), value.to_owned()));
+        self
+    }
+
+    /// Send requests through a proxy, e.g. `http://proxy:3128` or `socks5://bastion:1080`
+    ///
+    /// This overrides the kubeconfig's `proxy-url` and any proxy environment variables.
+    /// SOCKS5 proxies require the `socks` cargo feature.
+    pub fn proxy(mut self, url: &str) -> KubernetesBuilder {
+        self.options.proxy = Some(ProxyOverride::Url(url.to_owned()));
+        self
+    }
+
+    /// Connect directly, ignoring the kubeconfig's `proxy-url` and any proxy environment variables
+    pub fn no_proxy(mut self) -> KubernetesBuilder {
+        self.options.proxy = Some(ProxyOverride::Disabled);
+        self
+    }
+
+    /// Set the policy for retrying transient failures
+    ///
+    /// See [`RetryPolicy`](struct.RetryPolicy.html) for the default.
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> KubernetesBuilder {
+        self.options.retry = policy;
+        self
+    }
+
+    /// Limit requests to `qps` per second on average, with bursts of up to `burst` requests
+    ///
+    /// The limit is shared by all clones of the built client. It defaults to
+    /// 5 requests per second with bursts of 10, matching client-go.
+    /// A non-positive `qps` disables rate limiting.
+    pub fn rate_limit(mut self, qps: f32, burst: u32) -> KubernetesBuilder {
+        self.options.rate_limiter = RateLimiter::new(qps, burst);
+        self
+    }
+
+    /// Don't limit the rate of requests
+    pub fn no_rate_limit(mut self) -> KubernetesBuilder {
+        self.options.rate_limiter = RateLimiter::unlimited();
+        self
+    }
+
+    /// Keep refreshed `auth-provider` tokens in memory instead of
+    /// writing them back to the kubeconfig files they were loaded from
+    pub fn no_persist_tokens(mut self) -> KubernetesBuilder {
+        self.context.config_paths.clear();
+        self
+    }
+
+    /// Send requests with a custom `Transport` instead of `reqwest`
+    ///
+    /// Credentials, impersonation, headers, retries, and rate limiting are still
+    /// handled by the client, but the cluster's TLS and proxy settings and the
+    /// timeout are up to the transport. The async client doesn't support transports.
+    pub fn transport<T: Transport + 'static>(mut self, transport: T) -> KubernetesBuilder {
+        self.options.transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// Add hooks to run around every request
+    ///
+    /// Middleware runs in the order it was added. The async client doesn't support middleware.
+    pub fn middleware<M: Middleware + 'static>(mut self, middleware: M) -> KubernetesBuilder {
+        self.options.middleware.push(Arc::new(middleware));
+        self
+    }
+
+    /// Log requests and responses with the `log` crate, like `kubectl -v`
+    ///
+    /// See [`LoggingMiddleware`](struct.LoggingMiddleware.html) for what each verbosity logs.
+    /// This is middleware, so the async client doesn't support it.
+    pub fn verbosity(self, verbosity: u8) -> KubernetesBuilder {
+        self.middleware(LoggingMiddleware::new(verbosity))
+    }
+
+    /// Build the `Kubernetes` client
+    pub fn build(self) -> Result<Kubernetes> {
+        let default_namespace = self.context.namespace.clone();
+        let low_level = KubeLowLevel::new(self.context, &self.options)?;
+        Ok(Kubernetes::with_low_level(low_level, default_namespace))
+    }
+
+    /// Build an `AsyncKubernetes` client
+    ///
+    /// This requires the `async` cargo feature. It fails if a `transport`,
+    /// `middleware`, or `verbosity` is set, since the async client doesn't support them.
+    #[cfg(feature = "async")]
+    pub fn build_async(self) -> Result<AsyncKubernetes> {
+        if self.options.transport.is_some() {
+            bail!("The async client doesn't support custom transports");
+        }
+        if !self.options.middleware.is_empty() {
+            bail!("The async client doesn't support middleware, including verbosity logging");
+        }
+        let default_namespace = self.context.namespace.clone();
+        let settings = ClientSettings::new(self.context, &self.options)?;
+        AsyncKubernetes::new(settings, default_namespace)
+    }
+}
@@ -0,0 +1,172 @@
+use std::collections::BTreeMap;
+use std::sync::MutexGuard;
+use super::low_level::KubeLowLevel;
+use resources::ApiResource;
+use errors::*;
+
+/// How a kind of resource is served by the API server, as found by discovery
+#[derive(Clone, Debug)]
+pub struct RestMapping {
+    /// Where resources of this kind are served
+    pub resource: ApiResource,
+    /// The kind, e.g. `Deployment`
+    pub kind: String,
+    /// The supported verbs, e.g. `get`, `list`, and `create`
+    pub verbs: Vec<String>,
+    /// Abbreviations accepted by `kubectl`, e.g. `deploy`
+    pub short_names: Vec<String>,
+}
+
+/// Maps the kinds in each API group version to the resources that serve them
+///
+/// See [`Kubernetes::discover`](struct.Kubernetes.html#method.discover).
+#[derive(Clone, Debug, Default)]
+pub struct RestMapper {
+    group_versions: BTreeMap<String, Vec<RestMapping>>,
+}
+
+impl RestMapper {
+    /// Finds the mapping for `kind` in `api_version`, e.g. `Deployment` in `apps/v1`
+    pub fn mapping(&self, api_version: &str, kind: &str) -> Option<&RestMapping> {
+        self.group_versions.get(api_version)?
+            .iter()
+            .find(|mapping| mapping.kind == kind)
+    }
+
+    /// All discovered group versions, e.g. `v1` and `apps/v1`
+    pub fn group_versions(&self) -> impl Iterator<Item = &str> {
+        self.group_versions.keys().map(String::as_str)
+    }
+
+    /// All discovered mappings
+    pub fn mappings(&self) -> impl Iterator<Item = &RestMapping> {
+        self.group_versions.values().flat_map(|mappings| mappings.iter())
+    }
+
+    fn insert(&mut self, api_version: String, mappings: Vec<RestMapping>) {
+        self.group_versions.insert(api_version, mappings);
+    }
+}
+
+// These are the discovery responses, e.g. `APIGroupList`, trimmed to the fields we use
+
+#[derive(Deserialize, Debug)]
+struct ApiVersions {
+    versions: Vec<String>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ApiGroupList {
+    groups: Vec<ApiGroup>,
+}
+
+#[derive(Deserialize, Debug)]
+struct ApiGroup {
+    versions: Vec<GroupVersionForDiscovery>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct GroupVersionForDiscovery {
+    group_version: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct ApiResourceList {
+    resources: Vec<DiscoveredResource>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct DiscoveredResource {
+    name: String,
+    namespaced: bool,
+    kind: String,
+    #[serde(default)]
+    verbs: Vec<String>,
+    #[serde(default)]
+    short_names: Vec<String>,
+}
+
+impl KubeLowLevel {
+    /// Discovers all group versions, replacing the cached mappings
+    pub(crate) fn discover(&self) -> Result<RestMapper> {
+        let core: ApiVersions = self.http_get_json(self.base_url.join("/api")?)
+            .chain_err(|| "Failed to discover core API versions")?;
+        let groups: ApiGroupList = self.http_get_json(self.base_url.join("/apis")?)
+            .chain_err(|| "Failed to discover API groups")?;
+
+        let group_versions = core.versions.into_iter()
+            .chain(groups.groups.into_iter()
+                .flat_map(|group| group.versions)
+                .map(|version| version.group_version));
+
+        let mut mapper = RestMapper::default();
+        for api_version in group_versions {
+            // Like kubectl, don't let one broken group version (e.g. an unavailable
+            // aggregated API) hide all the others
+            match self.discover_group_version(&api_version) {
+                Ok(mappings) => mapper.insert(api_version, mappings),
+                Err(err) => warn!("Skipping discovery of {}: {}", api_version, err),
+            }
+        }
+
+        *self.lock_rest_mapper() = mapper.clone();
+        Ok(mapper)
+    }
+
+    /// Finds how `kind` in `api_version` is served
+    ///
+    /// Each group version is discovered the first time it's needed, and again
+    /// if it doesn't have `kind`, since it may have been added by a new CRD.
+    pub(crate) fn rest_mapping(&self, api_version: &str, kind: &str) -> Result<RestMapping> {
+        if let Some(mapping) = self.lock_rest_mapper().mapping(api_version, kind) {
+            return Ok(mapping.clone());
+        }
+
+        let mappings = self.discover_group_version(api_version)?;
+        let mut mapper = self.lock_rest_mapper();
+        mapper.insert(api_version.to_owned(), mappings);
+        match mapper.mapping(api_version, kind) {
+            Some(mapping) => Ok(mapping.clone()),
+            None => bail!("The server doesn't have kind {} in {}", kind, api_version),
+        }
+    }
+
+    fn discover_group_version(&self, api_version: &str) -> Result<Vec<RestMapping>> {
+        let (group, version) = match api_version.find('/') {
+            Some(i) => (&api_version[..i], &api_version[i + 1..]),
+            None => ("", api_version),
+        };
+        let api = ApiResource {
+            group: group.to_owned(),
+            version: version.to_owned(),
+            plural: String::new(),
+            namespaced: false,
+        };
+
+        let url = self.base_url.join(&api.api_path())?;
+        let list: ApiResourceList = self.http_get_json(url)
+            .chain_err(|| format!("Failed to discover resources in {}", api_version))?;
+
+        let mappings = list.resources.into_iter()
+            // Subresources like `deployments/scale` aren't kinds of their own
+            .filter(|resource| !resource.name.contains('/'))
+            .map(|resource| RestMapping {
+                resource: ApiResource {
+                    plural: resource.name,
+                    namespaced: resource.namespaced,
+                    ..api.clone()
+                },
+                kind: resource.kind,
+                verbs: resource.verbs,
+                short_names: resource.short_names,
+            })
+            .collect();
+        Ok(mappings)
+    }
+
+    fn lock_rest_mapper(&self) -> MutexGuard<'_, RestMapper> {
+        self.rest_mapper.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
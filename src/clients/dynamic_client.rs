@@ -0,0 +1,112 @@
+use super::{kind_namespace, Kubernetes};
+use super::low_level::{KindRoute, ResourceRoute};
+use resources::*;
+use errors::*;
+
+/// A client for managing resources of any kind as `DynamicObject`s
+///
+/// This allows managing kinds that don't have a type in this crate,
+/// like custom resources. See [`Kubernetes::dynamic`](struct.Kubernetes.html#method.dynamic).
+pub struct DynamicClient {
+    pub(super) kube: Kubernetes,
+    pub(super) resource: ApiResource,
+}
+
+impl DynamicClient {
+    /// Get a client that uses a specific namespace
+    pub fn namespace(&self, namespace: &str) -> DynamicClient {
+        DynamicClient { kube: self.kube.namespace(namespace), resource: self.resource.clone() }
+    }
+
+    /// Indicates whether or not the named resource exists in the Kubernetes cluster
+    pub fn exists(&self, name: &str) -> Result<bool> {
+        let api = self.resource.api_path();
+        let mut route = ResourceRoute::new(&api, &self.resource.plural, name);
+        if let Some(ns) = self.namespace_for(None) {
+            route.namespace(ns);
+        }
+        self.kube.low_level.exists(&route)
+    }
+
+    /// Gets the named resource
+    pub fn get(&self, name: &str) -> Result<DynamicObject> {
+        let api = self.resource.api_path();
+        let mut route = ResourceRoute::new(&api, &self.resource.plural, name);
+        if let Some(ns) = self.namespace_for(None) {
+            route.namespace(ns);
+        }
+        self.kube.low_level.get(&route)
+    }
+
+    /// Lists resources of this kind
+    pub fn list(&self, query: Option<&ListQuery>) -> Result<Vec<DynamicObject>> {
+        let api = self.resource.api_path();
+        let mut route = KindRoute::new(&api, &self.resource.plural);
+        if let Some(ns) = self.namespace_for(None) {
+            route.namespace(ns);
+        }
+        if let Some(query) = query {
+            route.query(query.as_query_pairs());
+        }
+        let list: DynamicObjectList = self.kube.low_level.list(&route)?;
+        Ok(list.into_items())
+    }
+
+    /// Creates a resource
+    ///
+    /// The resource's `metadata.namespace` is used if set, instead of the client's namespace.
+    pub fn create(&self, resource: &DynamicObject) -> Result<DynamicObject> {
+        let api = self.resource.api_path();
+        let mut route = KindRoute::new(&api, &self.resource.plural);
+        if let Some(ns) = self.namespace_for(resource.metadata.namespace.as_ref()) {
+            route.namespace(ns);
+        }
+        self.kube.low_level.apply(&route, resource)
+    }
+
+    /// Replaces the resource with the same name
+    ///
+    /// The resource's `metadata.namespace` is used if set, instead of the client's namespace.
+    pub fn replace(&self, resource: &DynamicObject) -> Result<DynamicObject> {
+        let name = match resource.metadata.name {
+            Some(ref name) => name,
+            None => bail!("must set metadata.name to replace kubernetes resource"),
+        };
+        let api = self.resource.api_path();
+        let mut route = ResourceRoute::new(&api, &self.resource.plural, name);
+        if let Some(ns) = self.namespace_for(resource.metadata.namespace.as_ref()) {
+            route.namespace(ns);
+        }
+        self.kube.low_level.replace(&route, resource)
+    }
+
+    /// Patches the named resource, returning the updated resource
+    pub fn patch(&self, name: &str, patch: Patch) -> Result<DynamicObject> {
+        let api = self.resource.api_path();
+        let mut route = ResourceRoute::new(&api, &self.resource.plural, name);
+        if let Some(ns) = self.namespace_for(None) {
+            route.namespace(ns);
+        }
+        self.kube.low_level.patch(&route, &patch)
+    }
+
+    /// Deletes the named resource
+    pub fn delete(&self, name: &str) -> Result<()> {
+        let api = self.resource.api_path();
+        let mut route = ResourceRoute::new(&api, &self.resource.plural, name);
+        if let Some(ns) = self.namespace_for(None) {
+            route.namespace(ns);
+        }
+        self.kube.low_level.delete(&route)
+    }
+
+    fn namespace_for<'a>(&'a self, resource_namespace: Option<&'a String>) -> Option<&'a str> {
+        if !self.resource.namespaced {
+            return None;
+        }
+        match resource_namespace {
+            Some(ns) => Some(ns),
+            None => kind_namespace(Some("default"), self.kube.namespace.as_ref(), self.kube.default_namespace.as_ref()),
+        }
+    }
+}
@@ -1,25 +1,65 @@
-use reqwest::{self, header, StatusCode};
-use headers_ext::{self, HeaderMapExt};
+use http::{Request, Response};
+use reqwest::{self, header, Method, StatusCode};
+use super::auth::{Auth, Impersonation};
+use super::discovery::RestMapper;
+use super::middleware::Middleware;
+use super::rate_limit::RateLimiter;
+use super::retry::RetryPolicy;
+use super::transport::{ReqwestTransport, Transport};
 use std::path::Path;
-use config::KubeConfig;
+use config::ClusterContext;
 use resources::*;
 use std::fs::File;
 use std::io::Read;
 use openssl::pkcs12::Pkcs12;
+use openssl::pkey::{PKey, Private};
+use openssl::x509::X509;
 use serde::Serialize;
 use serde::de::DeserializeOwned;
 use serde_json::{self, Value};
 use serde_yaml;
 use url::Url;
 use std::borrow::Borrow;
+use std::env;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
 use walkdir::WalkDir;
 use errors::*;
 use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
 
 #[derive(Clone)]
 pub struct KubeLowLevel {
-    pub(crate) client: reqwest::Client,
+    transport: Arc<dyn Transport>,
     pub(crate) base_url: Url,
+    headers: header::HeaderMap,
+    middleware: Vec<Arc<dyn Middleware>>,
+    auth: Auth,
+    pub(crate) impersonation: Impersonation,
+    pub(crate) retry: RetryPolicy,
+    pub(crate) rate_limiter: RateLimiter,
+    pub(crate) rest_mapper: Arc<Mutex<RestMapper>>,
+}
+
+/// Options for the underlying HTTP client that aren't part of a kubeconfig
+#[derive(Clone, Default)]
+pub(crate) struct ClientOptions {
+    pub(crate) timeout: Option<Duration>,
+    pub(crate) user_agent: Option<String>,
+    pub(crate) headers: Vec<(String, String)>,
+    pub(crate) proxy: Option<ProxyOverride>,
+    pub(crate) retry: RetryPolicy,
+    pub(crate) rate_limiter: RateLimiter,
+    pub(crate) transport: Option<Arc<dyn Transport>>,
+    pub(crate) middleware: Vec<Arc<dyn Middleware>>,
+}
+
+/// Overrides the proxy from the kubeconfig and environment
+#[derive(Clone, Debug)]
+pub(crate) enum ProxyOverride {
+    Url(String),
+    Disabled,
 }
 
 // This is only used for figuring out the API endpoint to use
@@ -27,72 +67,224 @@ pub struct KubeLowLevel {
 #[serde(rename_all = "camelCase")]
 struct MinimalResource {
     api_version: String,
-    kind: Kind,
+    kind: String,
     metadata: ObjectMeta,
 }
 
-impl KubeLowLevel {
-    pub fn load_conf<P: AsRef<Path>>(path: P) -> Result<KubeLowLevel> {
-        let kubeconfig = KubeConfig::load(path)?;
-        let context = kubeconfig.default_context()?;
+/// Connection settings shared by the blocking and async HTTP clients
+#[derive(Clone)]
+pub(crate) struct ClientSettings {
+    pub(crate) base_url: Url,
+    pub(crate) auth: Auth,
+    pub(crate) impersonation: Impersonation,
+    pub(crate) retry: RetryPolicy,
+    pub(crate) rate_limiter: RateLimiter,
+    pub(crate) root_cert: Option<reqwest::Certificate>,
+    /// The client certificate from the kubeconfig, see `identity` for one from a credential plugin
+    pub(crate) client_identity: Option<(X509, PKey<Private>)>,
+    pub(crate) accept_invalid_certs: bool,
+    pub(crate) headers: header::HeaderMap,
+    pub(crate) proxy: Option<reqwest::Proxy>,
+    pub(crate) timeout: Option<Duration>,
+}
+
+impl ClientSettings {
+    pub(crate) fn new(context: ClusterContext, options: &ClientOptions) -> Result<ClientSettings> {
+        let auth = Auth::from_context(&context)?;
+        let impersonation = Impersonation::from_auth_info(&context.user);
         let auth_info = context.user;
+        let (cluster_name, user_name) = (context.cluster_name, context.user_name);
 
         let cluster = context.cluster;
 
+        // Without a CA in the kubeconfig, the system roots are used
+        let ca_cert = cluster.ca_cert()
+            .chain_err(|| format!("Invalid certificate-authority for cluster {}", cluster_name))?;
+        let root_cert = match ca_cert {
+            Some(ca_cert) => {
+                let der = ca_cert.to_der().chain_err(|| "Failed to encode CA cert")?;
+                Some(reqwest::Certificate::from_der(&der).chain_err(|| "Invalid CA cert")?)
+            }
+            None => None,
+        };
+
+        let accept_invalid_certs = cluster.insecure_tls == Some(true);
+        // reqwest can neither send another SNI name nor verify the certificate
+        // against one, so keep verifying against the server host, which works
+        // whenever the certificate covers both names
+        if let Some(ref server_name) = cluster.tls_server_name {
+            if !accept_invalid_certs {
+                warn!("tls-server-name '{}' for cluster {} isn't supported: verifying the API server's \
+                       certificate against the host in its server URL instead", server_name, cluster_name);
+            }
+        }
+
+        let client_cert = auth_info.client_certificate()
+            .chain_err(|| format!("Invalid client-certificate for user {}", user_name))?;
+        let client_key = auth_info.client_key()
+            .chain_err(|| format!("Invalid client-key for user {}", user_name))?;
+        let client_identity = match (client_cert, client_key) {
+            (Some(crt), Some(key)) => Some((crt, key)),
+            _ => None,
+        };
+
         let mut headers = header::HeaderMap::new();
-        let client = reqwest::Client::builder();
-
-        let client = if let Some(ca_cert) = cluster.ca_cert() {
-            let req_ca_cert = reqwest::Certificate::from_der(&ca_cert.to_der().unwrap()).unwrap();
-            client.add_root_certificate(req_ca_cert)
-        } else { client };
-
-        let client = if auth_info.client_certificate().is_some() && auth_info.client_key().is_some() {
-            let crt = auth_info.client_certificate().unwrap();
-            let key = auth_info.client_key().unwrap();
-            let pkcs_cert = Pkcs12::builder().build("", "admin", &key, &crt).chain_err(|| "Failed to build Pkcs12")?;
-            let req_pkcs_cert = reqwest::Identity::from_pkcs12_der(&pkcs_cert.to_der().unwrap(), "").unwrap();
-            client.identity(req_pkcs_cert)
-        } else { client };
-
-        if let (Some(username), Some(password)) = (auth_info.username, auth_info.password) {
-            headers.typed_insert(headers_ext::Authorization::basic(
-                &username, &password
-            ));
-        } else if let Some(token) = auth_info.token {
-            headers.typed_insert(headers_ext::Authorization::bearer(&token)
-                                 .map_err(|_| Error::from("Invalid bearer token"))?);
+        if let Some(ref user_agent) = options.user_agent {
+            let value = header::HeaderValue::from_str(user_agent)
+                .chain_err(|| format!("Invalid user agent '{}'", user_agent))?;
+            headers.insert(header::USER_AGENT, value);
+        }
+        for (name, value) in &options.headers {
+            let name = header::HeaderName::from_bytes(name.as_bytes())
+                .chain_err(|| format!("Invalid header name '{}'", name))?;
+            let value = header::HeaderValue::from_str(value)
+                .chain_err(|| format!("Invalid value for header '{}'", name))?;
+            headers.append(name, value);
+        }
+
+        let proxy = match options.proxy {
+            Some(ProxyOverride::Url(ref url)) => Some(url.clone()),
+            Some(ProxyOverride::Disabled) => None,
+            None => cluster.proxy_url.clone().or_else(|| proxy_from_env(&cluster.server)),
+        };
+        let proxy = match proxy {
+            Some(url) => Some(reqwest::Proxy::all(&*url).chain_err(|| format!("Invalid proxy URL '{}'", url))?),
+            None => None,
+        };
+
+        Ok(ClientSettings {
+            base_url: cluster.server,
+            auth,
+            impersonation,
+            retry: options.retry.clone(),
+            rate_limiter: options.rate_limiter.clone(),
+            root_cert,
+            client_identity,
+            accept_invalid_certs,
+            headers,
+            proxy,
+            timeout: options.timeout,
+        })
+    }
+
+    /// The client certificate to authenticate with, either from the
+    /// kubeconfig or from a credential plugin
+    pub(crate) fn identity(&self) -> Result<Option<(X509, PKey<Private>)>> {
+        match self.client_identity {
+            Some(ref identity) => Ok(Some(identity.clone())),
+            None => self.auth.client_identity(),
         }
+    }
 
-        let client = client.default_headers(headers)
-                           .build()
-                           .chain_err(|| "Failed to build reqwest client")?;
+    fn reqwest_client(&self, identity: Option<(X509, PKey<Private>)>) -> Result<reqwest::Client> {
+        let mut client = reqwest::Client::builder()
+            .danger_accept_invalid_certs(self.accept_invalid_certs);
+        if let Some(ref root_cert) = self.root_cert {
+            client = client.add_root_certificate(root_cert.clone());
+        }
+        if let Some((crt, key)) = identity {
+            client = client.identity(pkcs12_identity(&crt, &key)?);
+        }
+        if let Some(ref proxy) = self.proxy {
+            client = client.proxy(proxy.clone());
+        }
+        if let Some(timeout) = self.timeout {
+            client = client.timeout(timeout);
+        }
+        client.build().chain_err(|| "Failed to build reqwest client")
+    }
+}
+
+pub(crate) fn pkcs12_identity(crt: &X509, key: &PKey<Private>) -> Result<reqwest::Identity> {
+    let pkcs_cert = Pkcs12::builder().build("", "admin", key, crt).chain_err(|| "Failed to build Pkcs12")?;
+    let der = pkcs_cert.to_der().chain_err(|| "Failed to encode Pkcs12")?;
+    reqwest::Identity::from_pkcs12_der(&der, "").chain_err(|| "Invalid client identity")
+}
 
-        Ok(KubeLowLevel { client, base_url: cluster.server })
+/// The default transport when credentials come from an exec plugin
+///
+/// reqwest only takes a client certificate when a client is built, so the
+/// client is rebuilt whenever the plugin is re-run and returns a new certificate.
+struct ExecIdentityTransport {
+    settings: ClientSettings,
+    // The auth generation the client was built for, and whether it has a certificate
+    current: Mutex<(u64, bool, Arc<ReqwestTransport>)>,
+}
+
+impl ExecIdentityTransport {
+    fn new(settings: ClientSettings) -> Result<ExecIdentityTransport> {
+        let generation = settings.auth.generation();
+        let identity = settings.identity()?;
+        let has_identity = identity.is_some();
+        let transport = Arc::new(ReqwestTransport::new(settings.reqwest_client(identity)?));
+        Ok(ExecIdentityTransport { settings, current: Mutex::new((generation, has_identity, transport)) })
+    }
+
+    fn transport(&self) -> Result<Arc<ReqwestTransport>> {
+        let mut current = self.current.lock().unwrap_or_else(|err| err.into_inner());
+        let generation = self.settings.auth.generation();
+        if current.0 != generation {
+            let identity = self.settings.identity()?;
+            // Plugins that only return tokens keep the client and its connections
+            if identity.is_some() || current.1 {
+                let has_identity = identity.is_some();
+                let client = self.settings.reqwest_client(identity)?;
+                *current = (generation, has_identity, Arc::new(ReqwestTransport::new(client)));
+            } else {
+                current.0 = generation;
+            }
+        }
+        Ok(current.2.clone())
+    }
+}
+
+impl Transport for ExecIdentityTransport {
+    fn send(&self, request: Request<Vec<u8>>) -> Result<Response<Vec<u8>>> {
+        self.transport()?.send(request)
+    }
+}
+
+impl KubeLowLevel {
+    pub(crate) fn new(context: ClusterContext, options: &ClientOptions) -> Result<KubeLowLevel> {
+        let settings = ClientSettings::new(context, options)?;
+
+        let transport: Arc<dyn Transport> = match options.transport {
+            Some(ref transport) => transport.clone(),
+            None => match settings.auth {
+                Auth::Exec(_) if settings.client_identity.is_none() => {
+                    Arc::new(ExecIdentityTransport::new(settings.clone())?)
+                }
+                _ => Arc::new(ReqwestTransport::new(settings.reqwest_client(settings.client_identity.clone())?)),
+            },
+        };
+
+        Ok(KubeLowLevel {
+            transport,
+            base_url: settings.base_url,
+            headers: settings.headers,
+            middleware: options.middleware.clone(),
+            auth: settings.auth,
+            impersonation: settings.impersonation,
+            retry: settings.retry,
+            rate_limiter: settings.rate_limiter,
+            rest_mapper: Arc::new(Mutex::new(RestMapper::default())),
+        })
     }
 
     pub fn health(&self) -> Result<String> {
-        let mut response = self.http_get(self.base_url.join("healthz")?)?;
-        let mut output = String::new();
-        let _ = response.read_to_string(&mut output)?;
-        Ok(output)
+        let response = self.http_get(self.base_url.join("healthz")?)?;
+        Ok(String::from_utf8_lossy(response.body()).into_owned())
     }
 
 
     pub fn exists(&self, route: &ResourceRoute) -> Result<bool> {
         let url = route.build(&self.base_url)?;
-        let mut response = self.client.get(url)
-            .send()
-            .chain_err(|| "Failed to GET URL")?;
+        let response = self.send(Method::GET, url, None)?;
 
         match response.status() {
             StatusCode::NOT_FOUND => Ok(false),
             s if s.is_success() => Ok(true),
-            _ => {
-                let status: Status = response.json()
-                    .chain_err(|| "Failed to decode error response as 'Status'")?;
-                bail!(status.message);
-            }
+            _ => Err(api_error(response)),
         }
     }
 
@@ -128,6 +320,21 @@ impl KubeLowLevel {
         self.http_post_json(url, &body)
     }
 
+    pub fn replace<S, D>(&self, route: &ResourceRoute, body: &S) -> Result<D>
+    where S: Serialize,
+          D: DeserializeOwned
+    {
+        let url = route.build(&self.base_url)?;
+        self.http_put_json(url, &body)
+    }
+
+    pub fn patch<D>(&self, route: &ResourceRoute, patch: &Patch) -> Result<D>
+    where D: DeserializeOwned
+    {
+        let url = route.build(&self.base_url)?;
+        self.http_patch_json(url, patch)
+    }
+
     pub(crate) fn each_resource_path<D, F, P: AsRef<Path>>(&self, path: P, handler: F) -> Result<Vec<D>>
     where
         D: DeserializeOwned + ::std::fmt::Debug,
@@ -147,37 +354,14 @@ impl KubeLowLevel {
             .collect()
     }
 
-    // TODO: This function could use a serious refactoring
-    pub(crate) fn apply_file<D>(&self, path: &Path) -> Result<D>
+    pub(crate) fn apply_file<D>(&self, path: &Path, default_namespace: Option<&str>) -> Result<D>
     where D: DeserializeOwned + ::std::fmt::Debug
     {
-        let mut bytes = Vec::new();
-        let ext = path.extension().unwrap().to_string_lossy().to_lowercase();
-        let mut file = File::open(path)?;
-        file.read_to_end(&mut bytes)?;
-        let body: Value = match &*ext {
-            "json" => serde_json::from_slice(&bytes)?,
-            "yaml" => serde_yaml::from_slice(&bytes)?,
-            _ => unreachable!("kubeclient bug: unexpected and unfiltered file extension"),
-        };
-        let mini: MinimalResource = serde_json::from_value(body.clone())?;
-
-        let root = if mini.api_version.starts_with("v") {
-            "/api"
-        } else {
-            "/apis"
-        };
-        let name = mini.metadata.name.expect("must set metadata.name to apply kubernetes resource");
-        let kind_path = match mini.metadata.namespace.as_ref().map(|x| &**x).borrow().or(mini.kind.default_namespace) {
-            Some(ns) => format!("{}/{}/namespaces/{}/{}", root, mini.api_version, ns, mini.kind.plural),
-            None =>format!("{}/{}/{}", root, mini.api_version, mini.kind.plural),
-        };
-        let kind_url = self.base_url.join(&kind_path)?;
-        let resource_url = self.base_url.join(&format!("{}/{}", kind_path, name))?;
+        let body = read_manifest(path)?;
+        let (kind_url, resource_url) = self.manifest_urls(&body, default_namespace)?;
 
         // First check if resource already exists
-        let mut response = self.client.get(resource_url).send()
-            .chain_err(|| "Failed to GET URL")?;
+        let response = self.send(Method::GET, resource_url, None)?;
         match response.status() {
             // Apply if resource doesn't exist
             StatusCode::NOT_FOUND => {
@@ -185,49 +369,43 @@ impl KubeLowLevel {
                 Ok(resp)
             }
             // Return it if it already exists
-            s if s.is_success() => {
-                let resp = response.json().chain_err(|| "Failed to decode JSON response")?;
-                Ok(resp)
-            }
+            s if s.is_success() => decode_json(&response),
             // Propogate any other error
-            _ => {
-                let status: Status = response.json()
-                    .chain_err(|| "Failed to decode error response as 'Status'")?;
-                bail!(status.message);
-            }
+            _ => Err(api_error(response)),
         }
     }
 
-    pub(crate) fn replace_file<D>(&self, path: &Path) -> Result<D>
+    pub(crate) fn replace_file<D>(&self, path: &Path, default_namespace: Option<&str>) -> Result<D>
     where D: DeserializeOwned + ::std::fmt::Debug
     {
-        let mut bytes = Vec::new();
-        let ext = path.extension().unwrap().to_string_lossy().to_lowercase();
-        let mut file = File::open(path)?;
-        file.read_to_end(&mut bytes)?;
-        let body: Value = match &*ext {
-            "json" => serde_json::from_slice(&bytes)?,
-            "yaml" => serde_yaml::from_slice(&bytes)?,
-            _ => unreachable!("kubeclient bug: unexpected and unfiltered file extension"),
-        };
-        let mini: MinimalResource = serde_json::from_value(body.clone())?;
+        let body = read_manifest(path)?;
+        let (_, resource_url) = self.manifest_urls(&body, default_namespace)?;
+        let resp = self.http_put_json(resource_url, &body)?;
+        Ok(resp)
+    }
 
-        let root = if mini.api_version.starts_with("v") {
-            "/api"
-        } else {
-            "/apis"
+    /// Finds the URLs of a manifest's kind and of the resource itself, using discovery
+    fn manifest_urls(&self, body: &Value, default_namespace: Option<&str>) -> Result<(Url, Url)> {
+        let mini: MinimalResource = serde_json::from_value(body.clone())?;
+        let name = match mini.metadata.name {
+            Some(name) => name,
+            None => bail!("must set metadata.name to apply kubernetes resource"),
         };
-        let name = mini.metadata.name.expect("must set metadata.name to apply kubernetes resource");
-        let url = match mini.metadata.namespace {
-            Some(ns) => self.base_url.join(
-                &format!("{}/{}/namespaces/{}/{}/{}", root, mini.api_version, ns, mini.kind.plural, name)
-                )?,
-            None => self.base_url.join(
-                &format!("{}/{}/{}/{}", root, mini.api_version, mini.kind.plural, name)
-                )?,
+        let mapping = self.rest_mapping(&mini.api_version, &mini.kind)?;
+        let resource = mapping.resource;
+
+        let api = resource.api_path();
+        let mut route = KindRoute::new(&api, &resource.plural);
+        let namespace = match mini.metadata.namespace {
+            Some(ref ns) => Some(&**ns),
+            None => default_namespace.or(Some("default")),
         };
-        let resp = self.http_put_json(url, &body)?;
-        Ok(resp)
+        if let (true, Some(ns)) = (resource.namespaced, namespace) {
+            route.namespace(ns);
+        }
+        let kind_url = route.build(&self.base_url)?;
+        let resource_url = kind_url.join(&format!("{}/{}", resource.plural, name))?;
+        Ok((kind_url, resource_url))
     }
 
     pub fn delete(&self, route: &ResourceRoute) -> Result<()> {
@@ -239,69 +417,136 @@ impl KubeLowLevel {
     // Low-level
     //
 
-    pub(crate) fn http_get(&self, url: Url) -> Result<reqwest::Response> {
-        let req = self.client.get(url);
+    /// Sends a request with the client's credentials
+    ///
+    /// Transient failures are retried according to the client's `RetryPolicy`.
+    pub(crate) fn send(&self, method: Method, url: Url, body: Option<Body>) -> Result<Response<Vec<u8>>> {
+        let mut attempt = 1;
+        loop {
+            let result = self.send_authenticated(method.clone(), url.clone(), body);
+            let outcome = result.as_ref().map(|r| (r.status(), r.headers()));
+            match self.retry.retry_delay(&method, outcome, attempt) {
+                Some(delay) => {
+                    drop(result);
+                    thread::sleep(delay);
+                    attempt += 1;
+                }
+                None => return result.chain_err(|| format!("Failed to {} URL", method)),
+            }
+        }
+    }
+
+    /// Sends a request, retrying once with fresh credentials
+    /// if the API server rejects credentials that can be refreshed
+    fn send_authenticated(&self, method: Method, url: Url, body: Option<Body>) -> Result<Response<Vec<u8>>> {
+        let response = self.send_once(method.clone(), url.clone(), body)?;
+        if response.status() == StatusCode::UNAUTHORIZED && self.auth.invalidate() {
+            return self.send_once(method, url, body);
+        }
+        Ok(response)
+    }
+
+    fn send_once(&self, method: Method, url: Url, body: Option<Body>) -> Result<Response<Vec<u8>>> {
+        self.rate_limiter.acquire();
+
+        let mut request = Request::new(body.map(|body| body.bytes.to_vec()).unwrap_or_default());
+        *request.method_mut() = method;
+        *request.uri_mut() = url.as_str().parse()
+            .chain_err(|| format!("Invalid request URL '{}'", url))?;
+
+        {
+            let headers = request.headers_mut();
+            *headers = self.headers.clone();
+            self.auth.apply(headers)?;
+            self.impersonation.apply(headers)?;
+            if let Some(body) = body {
+                headers.insert(header::CONTENT_TYPE, header::HeaderValue::from_static(body.content_type));
+            }
+        }
+
+        for middleware in &self.middleware {
+            middleware.before_request(&mut request)?;
+        }
+
+        let (method, uri) = (request.method().clone(), request.uri().clone());
+        let start = Instant::now();
+        match self.transport.send(request) {
+            Ok(mut response) => {
+                let elapsed = start.elapsed();
+                for middleware in &self.middleware {
+                    middleware.after_response(&method, &uri, &mut response, elapsed)?;
+                }
+                Ok(response)
+            }
+            Err(err) => {
+                let elapsed = start.elapsed();
+                for middleware in &self.middleware {
+                    middleware.on_error(&method, &uri, &err, elapsed);
+                }
+                Err(err)
+            }
+        }
+    }
 
-        let mut response = req.send().chain_err(|| "Failed to GET URL")?;
+    pub(crate) fn http_get(&self, url: Url) -> Result<Response<Vec<u8>>> {
+        let response = self.send(Method::GET, url, None)?;
 
         if !response.status().is_success() {
-            let status: Status = response.json()
-                .chain_err(|| "Failed to decode kubernetes error response as 'Status'")?;
-            bail!(format!("Kubernetes API error: {}", status.message));
+            return Err(api_error(response));
         }
         Ok(response)
     }
 
     pub(crate) fn http_get_json<D: DeserializeOwned>(&self, url: Url) -> Result<D> {
-        let mut response = self.http_get(url)?;
-        Ok(response.json().chain_err(|| "Failed to decode JSON response")?)
+        let response = self.http_get(url)?;
+        decode_json(&response)
     }
 
     pub(crate) fn http_post_json<S, D>(&self, url: Url, body: &S) -> Result<D>
     where S: Serialize,
           D: DeserializeOwned,
     {
-        let mut response = self.client.post(url)
-            .json(&body)
-            .send()
-            .chain_err(|| "Failed to POST URL")?;
+        let body = serde_json::to_vec(body)?;
+        let response = self.send(Method::POST, url, Some(Body::json(&body)))?;
 
         if !response.status().is_success() {
-            let status: Status = response.json()
-                .chain_err(|| "Failed to decode kubernetes error response as 'Status'")?;
-            bail!(format!("Kubernetes API error: {}", status.message));
+            return Err(api_error(response));
         }
 
-        Ok(response.json().chain_err(|| "Failed to decode JSON response")?)
+        decode_json(&response)
     }
 
     pub(crate) fn http_put_json<S, D>(&self, url: Url, body: &S) -> Result<D>
     where S: Serialize,
           D: DeserializeOwned,
     {
-        let mut response = self.client.put(url)
-            .json(&body)
-            .send()
-            .chain_err(|| "Failed to PUT URL")?;
+        let body = serde_json::to_vec(body)?;
+        let response = self.send(Method::PUT, url, Some(Body::json(&body)))?;
+
+        if !response.status().is_success() {
+            return Err(api_error(response));
+        }
+
+        decode_json(&response)
+    }
+
+    pub(crate) fn http_patch_json<D: DeserializeOwned>(&self, url: Url, patch: &Patch) -> Result<D> {
+        let body = serde_json::to_vec(patch.body())?;
+        let body = Body { content_type: patch.content_type(), bytes: &body };
+        let response = self.send(Method::PATCH, url, Some(body))?;
 
         if !response.status().is_success() {
-            let status: Status = response.json()
-                .chain_err(|| "Failed to decode kubernetes error response as 'Status'")?;
-            bail!(format!("Kubernetes API error: {}", status.message));
+            return Err(api_error(response));
         }
 
-        Ok(response.json().chain_err(|| "Failed to decode JSON response")?)
+        decode_json(&response)
     }
 
-    pub(crate) fn http_delete(&self, url: Url) -> Result<reqwest::Response> {
-        let mut response = self.client.delete(url)
-            .send()
-            .chain_err(|| "Failed to DELETE URL")?;
+    pub(crate) fn http_delete(&self, url: Url) -> Result<Response<Vec<u8>>> {
+        let response = self.send(Method::DELETE, url, None)?;
 
         if !response.status().is_success() {
-            let status: Status = response.json()
-                .chain_err(|| "Failed to decode kubernetes error response as 'Status'")?;
-            bail!(format!("Kubernetes API error: {}", status.message));
+            return Err(api_error(response));
         }
 
         Ok(response)
@@ -310,6 +555,145 @@ impl KubeLowLevel {
 }
 
 
+/// Reads a JSON or YAML manifest
+fn read_manifest(path: &Path) -> Result<Value> {
+    let mut bytes = Vec::new();
+    let ext = path.extension().unwrap().to_string_lossy().to_lowercase();
+    let mut file = File::open(path)?;
+    file.read_to_end(&mut bytes)?;
+    let body = match &*ext {
+        "json" => serde_json::from_slice(&bytes)?,
+        "yaml" => serde_yaml::from_slice(&bytes)?,
+        _ => unreachable!("kubeclient bug: unexpected and unfiltered file extension"),
+    };
+    Ok(body)
+}
+
+/// A request body and its content type
+#[derive(Clone, Copy)]
+pub(crate) struct Body<'a> {
+    content_type: &'static str,
+    bytes: &'a [u8],
+}
+
+impl<'a> Body<'a> {
+    fn json(bytes: &'a [u8]) -> Body<'a> {
+        Body { content_type: "application/json", bytes }
+    }
+}
+
+/// Longest error body kept in `ErrorKind::UnexpectedResponse`
+const MAX_ERROR_BODY: usize = 1024;
+
+/// Converts an unsuccessful response into an error
+///
+/// Kubernetes `Status` bodies become `ErrorKind::Api`. Anything else, like an
+/// HTML page from a proxy in front of the API server, becomes
+/// `ErrorKind::UnexpectedResponse` with the start of the body.
+fn api_error(response: Response<Vec<u8>>) -> Error {
+    error_from_body(response.status(), content_type(response.headers()), response.body())
+}
+
+fn decode_json<D: DeserializeOwned>(response: &Response<Vec<u8>>) -> Result<D> {
+    serde_json::from_slice(response.body()).chain_err(|| "Failed to decode JSON response")
+}
+
+pub(crate) fn content_type(headers: &header::HeaderMap) -> String {
+    headers.get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("")
+        .to_owned()
+}
+
+/// Converts the body of an unsuccessful response into an error
+pub(crate) fn error_from_body(code: StatusCode, content_type: String, bytes: &[u8]) -> Error {
+    let code = code.as_u16();
+    if let Ok(mut status) = serde_json::from_slice::<Status>(bytes) {
+        if status.kind == "Status" {
+            if status.code == 0 {
+                status.code = code;
+            }
+            return ErrorKind::Api(Box::new(status)).into();
+        }
+    }
+
+    let body = truncate_body(bytes, MAX_ERROR_BODY);
+    ErrorKind::UnexpectedResponse(code, content_type, body).into()
+}
+
+/// Decodes `bytes` as UTF-8 text, cut off after `max` bytes
+pub(crate) fn truncate_body(bytes: &[u8], max: usize) -> String {
+    let mut body = String::from_utf8_lossy(bytes).into_owned();
+    if body.len() > max {
+        let mut end = max;
+        while !body.is_char_boundary(end) {
+            end -= 1;
+        }
+        body.truncate(end);
+        body.push_str("...");
+    }
+    body
+}
+
+/// Finds the proxy for `server` from the standard proxy environment variables
+fn proxy_from_env(server: &Url) -> Option<String> {
+    let var = |name: &str| {
+        env::var(name)
+            .or_else(|_| env::var(name.to_lowercase()))
+            .ok()
+            .filter(|v| !v.is_empty())
+    };
+
+    let proxy = match server.scheme() {
+        "https" => var("HTTPS_PROXY"),
+        _ => var("HTTP_PROXY"),
+    };
+    let proxy = proxy.or_else(|| var("ALL_PROXY"))?;
+
+    let host = server.host_str()?.trim_start_matches('[').trim_end_matches(']');
+    let no_proxy = var("NO_PROXY").unwrap_or_default();
+    let excluded = no_proxy.split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .any(|entry| no_proxy_matches(entry, host));
+
+    if excluded { None } else { Some(proxy) }
+}
+
+/// Matches a `NO_PROXY` entry: `*`, a domain (and its subdomains), an IP, or a CIDR block
+fn no_proxy_matches(entry: &str, host: &str) -> bool {
+    if entry == "*" {
+        return true;
+    }
+
+    let entry = entry.trim_start_matches('[').trim_end_matches(']');
+    if let (Some(slash), Ok(ip)) = (entry.find('/'), host.parse::<IpAddr>()) {
+        let (net, bits) = entry.split_at(slash);
+        return match (net.parse::<IpAddr>(), bits[1..].parse::<u32>()) {
+            (Ok(IpAddr::V4(net)), Ok(bits)) if bits <= 32 => match ip {
+                IpAddr::V4(ip) => {
+                    let mask = (!0u32).checked_shl(32 - bits).unwrap_or(0);
+                    u32::from(ip) & mask == u32::from(net) & mask
+                }
+                IpAddr::V6(_) => false,
+            },
+            (Ok(IpAddr::V6(net)), Ok(bits)) if bits <= 128 => match ip {
+                IpAddr::V6(ip) => {
+                    let mask = (!0u128).checked_shl(128 - bits).unwrap_or(0);
+                    u128::from(ip) & mask == u128::from(net) & mask
+                }
+                IpAddr::V4(_) => false,
+            },
+            _ => false,
+        };
+    }
+
+    let domain = entry.trim_start_matches('.');
+    host.eq_ignore_ascii_case(domain)
+        || (host.len() > domain.len()
+            && host.to_lowercase().ends_with(&format!(".{}", domain.to_lowercase())))
+}
+
 pub struct KindRoute<'a> {
     api: &'a str,
     namespace: Option<&'a str>,
@@ -416,3 +800,65 @@ impl<'a> ResourceRoute<'a> {
         Ok(url)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_proxy_matches_wildcard() {
+        assert!(no_proxy_matches("*", "example.com"));
+        assert!(no_proxy_matches("*", "10.1.2.3"));
+    }
+
+    #[test]
+    fn no_proxy_matches_domains() {
+        assert!(no_proxy_matches("example.com", "example.com"));
+        assert!(no_proxy_matches("example.com", "api.Example.com"));
+        assert!(no_proxy_matches(".example.com", "example.com"));
+        assert!(no_proxy_matches(".example.com", "api.example.com"));
+        assert!(!no_proxy_matches("example.com", "badexample.com"));
+        assert!(!no_proxy_matches("api.example.com", "example.com"));
+    }
+
+    #[test]
+    fn no_proxy_matches_ipv4() {
+        assert!(no_proxy_matches("10.1.2.3", "10.1.2.3"));
+        assert!(no_proxy_matches("10.0.0.0/8", "10.1.2.3"));
+        assert!(!no_proxy_matches("10.0.0.0/8", "11.1.2.3"));
+        assert!(no_proxy_matches("0.0.0.0/0", "192.168.1.1"));
+        assert!(no_proxy_matches("10.1.2.3/32", "10.1.2.3"));
+        assert!(!no_proxy_matches("10.1.2.3/32", "10.1.2.4"));
+        assert!(!no_proxy_matches("10.0.0.0/33", "10.1.2.3"));
+        assert!(!no_proxy_matches("10.0.0.0/8", "example.com"));
+    }
+
+    #[test]
+    fn no_proxy_matches_ipv6() {
+        assert!(no_proxy_matches("::1", "::1"));
+        assert!(no_proxy_matches("[::1]", "::1"));
+        assert!(no_proxy_matches("fd00::/8", "fd00::1"));
+        assert!(!no_proxy_matches("fd00::/8", "fe80::1"));
+        assert!(no_proxy_matches("::/0", "fe80::1"));
+        assert!(no_proxy_matches("fd00::1/128", "fd00::1"));
+        assert!(!no_proxy_matches("fd00::/8", "10.1.2.3"));
+        assert!(!no_proxy_matches("10.0.0.0/8", "fd00::1"));
+    }
+
+    // Environment variables are process-wide, so the proxy variables are only set by this test
+    #[test]
+    fn proxy_from_env_honors_no_proxy() {
+        env::set_var("HTTPS_PROXY", "http://proxy:3128");
+        env::set_var("NO_PROXY", "internal.example.com, 10.0.0.0/8,[fd00::1]");
+        let proxy = |server: &str| proxy_from_env(&Url::parse(server).unwrap());
+
+        assert_eq!(proxy("https://api.example.com"), Some("http://proxy:3128".to_owned()));
+        assert_eq!(proxy("https://k8s.internal.example.com:6443"), None);
+        assert_eq!(proxy("https://10.1.2.3:6443"), None);
+        assert_eq!(proxy("https://[fd00::1]:6443"), None);
+        assert_eq!(proxy("https://[fd00::2]:6443"), Some("http://proxy:3128".to_owned()));
+
+        env::remove_var("HTTPS_PROXY");
+        env::remove_var("NO_PROXY");
+    }
+}
@@ -0,0 +1,153 @@
+use http::{HeaderMap, Method, Request, Response, Uri};
+use reqwest::header;
+use std::time::Duration;
+use super::low_level::truncate_body;
+use super::duration_ms;
+use errors::*;
+
+/// Hooks run around every request a `Kubernetes` client sends
+///
+/// Middleware is added with [`KubernetesBuilder::middleware`](struct.KubernetesBuilder.html#method.middleware),
+/// and runs in the order it was added. The hooks run once per attempt,
+/// so a retried request passes through them again.
+///
+/// ## Examples
+///
+/// ```no_run
+/// # extern crate http;
+/// # extern crate kubeclient;
+/// use http::{HeaderValue, Request};
+/// use kubeclient::{KubernetesBuilder, Middleware};
+/// use kubeclient::errors::Result;
+///
+/// struct RequestId;
+///
+/// impl Middleware for RequestId {
+///     fn before_request(&self, request: &mut Request<Vec<u8>>) -> Result<()> {
+///         request.headers_mut().insert("x-request-id", HeaderValue::from_static("abc123"));
+///         Ok(())
+///     }
+/// }
+///
+/// let kube = KubernetesBuilder::new("https://10.0.0.1:6443")?
+///     .middleware(RequestId)
+///     .build()?;
+/// ```
+pub trait Middleware: Send + Sync {
+    /// Called before a request is sent, after credentials have been added
+    ///
+    /// Returning an error fails the request without sending it.
+    fn before_request(&self, _request: &mut Request<Vec<u8>>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called after a response is received, including unsuccessful statuses
+    ///
+    /// Returning an error fails the request.
+    fn after_response(
+        &self,
+        _method: &Method,
+        _uri: &Uri,
+        _response: &mut Response<Vec<u8>>,
+        _elapsed: Duration,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called when a request fails without a response, e.g. if the connection was refused
+    fn on_error(&self, _method: &Method, _uri: &Uri, _error: &Error, _elapsed: Duration) {}
+}
+
+/// Longest body logged below verbosity 9
+const MAX_LOGGED_BODY: usize = 1024;
+
+/// Logs requests and responses with the `log` crate, like `kubectl -v`
+///
+/// Everything is logged at `debug` level under the `kubeclient::http` target,
+/// with more detail at higher verbosity:
+///
+/// - 6: method, URL, status, and latency
+/// - 7: request headers
+/// - 8: response headers, and request and response bodies truncated to 1KiB
+/// - 9: full request and response bodies
+///
+/// `Authorization` and `Proxy-Authorization` header values are redacted.
+#[derive(Clone, Debug)]
+pub struct LoggingMiddleware {
+    verbosity: u8,
+}
+
+impl LoggingMiddleware {
+    /// Log with kubectl-like `verbosity` between 6 and 9
+    pub fn new(verbosity: u8) -> LoggingMiddleware {
+        LoggingMiddleware { verbosity }
+    }
+
+    fn body(&self, body: &[u8]) -> String {
+        if self.verbosity >= 9 {
+            String::from_utf8_lossy(body).into_owned()
+        } else {
+            truncate_body(body, MAX_LOGGED_BODY)
+        }
+    }
+}
+
+impl Middleware for LoggingMiddleware {
+    fn before_request(&self, request: &mut Request<Vec<u8>>) -> Result<()> {
+        if self.verbosity >= 7 {
+            debug!(target: "kubeclient::http", "{} {}", request.method(), request.uri());
+            log_headers("Request", request.headers());
+        }
+        if self.verbosity >= 8 && !request.body().is_empty() {
+            debug!(target: "kubeclient::http", "Request Body: {}", self.body(request.body()));
+        }
+        Ok(())
+    }
+
+    fn after_response(
+        &self,
+        method: &Method,
+        uri: &Uri,
+        response: &mut Response<Vec<u8>>,
+        elapsed: Duration,
+    ) -> Result<()> {
+        if self.verbosity >= 6 {
+            debug!(
+                target: "kubeclient::http",
+                "{} {} {} in {} milliseconds",
+                method, uri, response.status(), duration_ms(elapsed)
+            );
+        }
+        if self.verbosity >= 8 {
+            log_headers("Response", response.headers());
+            debug!(target: "kubeclient::http", "Response Body: {}", self.body(response.body()));
+        }
+        Ok(())
+    }
+
+    fn on_error(&self, method: &Method, uri: &Uri, error: &Error, elapsed: Duration) {
+        if self.verbosity >= 6 {
+            debug!(
+                target: "kubeclient::http",
+                "{} {} failed in {} milliseconds: {}",
+                method, uri, duration_ms(elapsed), error
+            );
+        }
+    }
+}
+
+fn log_headers(prefix: &str, headers: &HeaderMap) {
+    for (name, value) in headers {
+        let value = value.to_str().unwrap_or("<binary>");
+        let value = if name == header::AUTHORIZATION || name == header::PROXY_AUTHORIZATION {
+            // Keep the scheme, e.g. "Bearer <masked>", like kubectl
+            match value.find(' ') {
+                Some(i) => format!("{} <masked>", &value[..i]),
+                None => "<masked>".to_owned(),
+            }
+        } else {
+            value.to_owned()
+        };
+        debug!(target: "kubeclient::http", "{} Header: {}: {}", prefix, name, value);
+    }
+}
@@ -1,14 +1,33 @@
+#[cfg(feature = "async")]
+pub mod async_clients;
+mod auth;
+mod builder;
+mod discovery;
+mod dynamic_client;
 mod low_level;
+mod middleware;
+mod rate_limit;
 mod resource_clients;
+mod retry;
+mod transport;
 
+pub use self::builder::KubernetesBuilder;
+pub use self::discovery::{RestMapper, RestMapping};
+pub use self::dynamic_client::DynamicClient;
+pub use self::middleware::{LoggingMiddleware, Middleware};
+pub use self::retry::RetryPolicy;
+pub use self::transport::{ReqwestTransport, Transport};
 pub use self::resource_clients::*;
 use self::low_level::*;
+use self::rate_limit::RateLimiter;
 
 use std::path::Path;
+use config::{ClusterContext, KubeConfig};
 use resources::*;
 use serde_json::Value;
 use errors::*;
 use std::marker::PhantomData;
+use std::time::Duration;
 
 
 /// The main type for instantiating clients for managing kubernetes resources
@@ -16,15 +35,16 @@ use std::marker::PhantomData;
 pub struct Kubernetes {
     pub(crate) low_level: KubeLowLevel,
     namespace: Option<String>,
+    default_namespace: Option<String>,
 }
 
 impl Kubernetes {
     /// Initialize a Kubernetes client from a Kubernets config file
     ///
-    /// **Incomplete**: `load_conf` was only implemented to meet
-    /// the needs of a single config, so it is currently hard-coded
-    /// to require a CA cert, a client cert, and skip hostname verification.
-    /// PRs for improving this are much appreciated.
+    /// This uses the config's `current-context`, including its default namespace.
+    /// TLS verification follows the cluster entry: the `certificate-authority` is
+    /// trusted if set (otherwise the system roots are used), and verification is
+    /// only skipped if `insecure-skip-tls-verify` is set.
     ///
     /// ## Examples
     ///
@@ -33,10 +53,115 @@ impl Kubernetes {
     /// let kube = Kubernetes::load_conf("admin.conf")?;
     /// ```
     pub fn load_conf<P: AsRef<Path>>(path: P) -> Result<Kubernetes> {
-        Ok(Kubernetes{
-            low_level: KubeLowLevel::load_conf(path)?,
-            namespace: None,
-        })
+        let kubeconfig = KubeConfig::load(path)?;
+        Kubernetes::from_context(kubeconfig.default_context()?)
+    }
+
+    /// Initialize a Kubernetes client from a named context in a Kubernetes config file
+    ///
+    /// Unlike `load_conf`, which always uses the config's `current-context`,
+    /// this allows a single process to talk to several clusters from one kubeconfig.
+    ///
+    /// ## Examples
+    ///
+    /// ```no_run
+    /// # use kubeclient::prelude::*;
+    /// let staging = Kubernetes::load_conf_with_context("admin.conf", "staging")?;
+    /// let production = Kubernetes::load_conf_with_context("admin.conf", "production")?;
+    /// ```
+    pub fn load_conf_with_context<P: AsRef<Path>>(path: P, context: &str) -> Result<Kubernetes> {
+        let kubeconfig = KubeConfig::load(path)?;
+        let context = kubeconfig.context(context)?;
+        Kubernetes::from_context(context)
+    }
+
+    /// Initialize a Kubernetes client from the kubeconfig files listed in `$KUBECONFIG`
+    ///
+    /// The files are merged following the same rules as `kubectl`,
+    /// falling back to `~/.kube/config` if `$KUBECONFIG` is not set.
+    /// See [`KubeConfig::load_merged`](../config/struct.KubeConfig.html#method.load_merged).
+    ///
+    /// ## Examples
+    ///
+    /// ```no_run
+    /// # use kubeclient::prelude::*;
+    /// let kube = Kubernetes::from_env()?;
+    /// ```
+    pub fn from_env() -> Result<Kubernetes> {
+        let kubeconfig = KubeConfig::load_merged()?;
+        Kubernetes::from_context(kubeconfig.default_context()?)
+    }
+
+    /// Initialize a Kubernetes client from the service account of the pod it is running in
+    ///
+    /// Namespaced resources default to the service account's namespace.
+    /// See [`ClusterContext::in_cluster`](../config/struct.ClusterContext.html#method.in_cluster).
+    ///
+    /// ## Examples
+    ///
+    /// ```no_run
+    /// # use kubeclient::prelude::*;
+    /// let kube = Kubernetes::in_cluster()?;
+    /// ```
+    pub fn in_cluster() -> Result<Kubernetes> {
+        Kubernetes::from_context(ClusterContext::in_cluster()?)
+    }
+
+    /// Initialize an in-cluster Kubernetes client reading service account credentials from `dir`
+    ///
+    /// This is the same as `in_cluster`, but allows overriding where the
+    /// service account `token`, `ca.crt`, and `namespace` files are read from.
+    ///
+    /// ## Examples
+    ///
+    /// ```no_run
+    /// # use kubeclient::prelude::*;
+    /// let kube = Kubernetes::from_service_account("/tmp/serviceaccount")?;
+    /// ```
+    pub fn from_service_account<P: AsRef<Path>>(dir: P) -> Result<Kubernetes> {
+        Kubernetes::from_context(ClusterContext::from_service_account(dir)?)
+    }
+
+    /// Initialize a Kubernetes client using in-cluster config, falling back to kubeconfig
+    ///
+    /// This first tries `in_cluster`, and if that fails, falls back to `from_env`.
+    ///
+    /// ## Examples
+    ///
+    /// ```no_run
+    /// # use kubeclient::prelude::*;
+    /// let kube = Kubernetes::infer()?;
+    /// ```
+    pub fn infer() -> Result<Kubernetes> {
+        match Kubernetes::in_cluster() {
+            Ok(kube) => Ok(kube),
+            Err(in_cluster_err) => Kubernetes::from_env().chain_err(|| {
+                format!("Unable to load in-cluster config ({}) or kubeconfig", in_cluster_err)
+            }),
+        }
+    }
+
+    /// Initialize a Kubernetes client from a kubeconfig context
+    ///
+    /// The context's namespace is used for namespaced resources,
+    /// unless overridden with `namespace`, matching `kubectl`.
+    /// To set options like the request timeout, use
+    /// [`KubernetesBuilder::from_context`](struct.KubernetesBuilder.html#method.from_context).
+    ///
+    /// ## Examples
+    ///
+    /// ```no_run
+    /// # use kubeclient::prelude::*;
+    /// # use kubeclient::KubeConfig;
+    /// let kubeconfig = KubeConfig::load("admin.conf")?;
+    /// let kube = Kubernetes::from_context(kubeconfig.context("staging")?)?;
+    /// ```
+    pub fn from_context(context: ClusterContext) -> Result<Kubernetes> {
+        KubernetesBuilder::from_context(context).build()
+    }
+
+    pub(crate) fn with_low_level(low_level: KubeLowLevel, default_namespace: Option<String>) -> Kubernetes {
+        Kubernetes { low_level, namespace: None, default_namespace }
     }
 
     /// Get a kubernetes client for managing `ConfigMaps`
@@ -144,6 +269,83 @@ impl Kubernetes {
         KubeClient { kube: self.clone(), _marker: PhantomData }
     }
 
+    /// Get a kubernetes client for managing resources of any type `R`,
+    /// e.g. a custom resource that derives `CustomResource`
+    ///
+    /// ## Examples
+    ///
+    /// ```no_run
+    /// # use kubeclient::prelude::*;
+    /// // Where `CronTab` derives `CustomResource`
+    /// let kube = Kubernetes::load_conf("admin.conf")?;
+    /// for crontab in kube.resources::<CronTab>().list(None)? {
+    ///     println!("Found crontab: {}", crontab.metadata.name.unwrap());
+    /// }
+    /// ```
+    pub fn resources<R: Resource>(&self) -> KubeClient<R> {
+        KubeClient { kube: self.clone(), _marker: PhantomData }
+    }
+
+    /// Get a kubernetes client for managing resources of any kind as `DynamicObject`s
+    ///
+    /// ## Examples
+    ///
+    /// ```no_run
+    /// # use kubeclient::prelude::*;
+    /// # use kubeclient::resources::ApiResource;
+    /// let kube = Kubernetes::load_conf("admin.conf")?;
+    /// let crontabs = kube.dynamic(ApiResource {
+    ///     group: "stable.example.com".to_owned(),
+    ///     version: "v1".to_owned(),
+    ///     plural: "crontabs".to_owned(),
+    ///     namespaced: true,
+    /// });
+    /// for crontab in crontabs.list(None)? {
+    ///     println!("Found crontab: {}", crontab.metadata.name.unwrap());
+    /// }
+    /// ```
+    pub fn dynamic(&self, resource: ApiResource) -> DynamicClient {
+        DynamicClient { kube: self.clone(), resource }
+    }
+
+    /// Discovers every kind of resource served by the API server
+    ///
+    /// This also refreshes the cached mappings used by `apply`, `replace`, and `rest_mapping`.
+    /// Group versions that fail to be discovered are logged and left out.
+    ///
+    /// Discovery makes a request for each group version, which counts against the client's
+    /// rate limit: at the default 5 requests per second, a cluster serving 40 group versions
+    /// takes around 6 seconds. Use [`rate_limit`](#method.rate_limit) to discover faster.
+    ///
+    /// ## Examples
+    ///
+    /// ```no_run
+    /// # use kubeclient::prelude::*;
+    /// let kube = Kubernetes::load_conf("admin.conf")?;
+    /// for mapping in kube.discover()?.mappings() {
+    ///     println!("{} is served as {}", mapping.kind, mapping.resource.plural);
+    /// }
+    /// ```
+    pub fn discover(&self) -> Result<RestMapper> {
+        self.low_level.discover()
+    }
+
+    /// Finds how `kind` in `api_version` is served, e.g. `CronTab` in `stable.example.com/v1`
+    ///
+    /// Discovery is cached and shared by clones of this client.
+    ///
+    /// ## Examples
+    ///
+    /// ```no_run
+    /// # use kubeclient::prelude::*;
+    /// let kube = Kubernetes::load_conf("admin.conf")?;
+    /// let mapping = kube.rest_mapping("stable.example.com/v1", "CronTab")?;
+    /// let crontabs = kube.dynamic(mapping.resource).list(None)?;
+    /// ```
+    pub fn rest_mapping(&self, api_version: &str, kind: &str) -> Result<RestMapping> {
+        self.low_level.rest_mapping(api_version, kind)
+    }
+
     /// Get a kubernetes client that uses a specific namespace
     ///
     /// ## Examples
@@ -156,7 +358,94 @@ impl Kubernetes {
     ///     .get("clusterinfo")?;
     /// ```
     pub fn namespace(&self, namespace: &str) -> Kubernetes {
-        Kubernetes { low_level: self.low_level.clone(), namespace: Some(namespace.to_owned()) }
+        Kubernetes {
+            low_level: self.low_level.clone(),
+            namespace: Some(namespace.to_owned()),
+            default_namespace: self.default_namespace.clone(),
+        }
+    }
+
+    /// Get a kubernetes client that impersonates another user
+    ///
+    /// Requests are sent with an `Impersonate-User` header, so they are authorized
+    /// as if they were made by `user`. This replaces any impersonation configured
+    /// by the kubeconfig's `as` field.
+    ///
+    /// ## Examples
+    ///
+    /// ```no_run
+    /// # use kubeclient::prelude::*;
+    /// let kube = Kubernetes::load_conf("admin.conf")?;
+    /// let can_read = kube.impersonate("alice")
+    ///     .groups(&["devs"])
+    ///     .secrets()
+    ///     .get("my-secret")
+    ///     .is_ok();
+    /// ```
+    pub fn impersonate(&self, user: &str) -> Kubernetes {
+        let mut kube = self.clone();
+        kube.low_level.impersonation.user = Some(user.to_owned());
+        kube.low_level.impersonation.groups.clear();
+        kube.low_level.impersonation.extra.clear();
+        kube
+    }
+
+    /// Get a kubernetes client that impersonates the given groups
+    ///
+    /// This sets the `Impersonate-Group` headers, and is only sent along with
+    /// an impersonated user, since the API server requires one.
+    pub fn groups(&self, groups: &[&str]) -> Kubernetes {
+        let mut kube = self.clone();
+        kube.low_level.impersonation.groups = groups.iter().map(|&g| g.to_owned()).collect();
+        kube
+    }
+
+    /// Get a kubernetes client that impersonates an extra user field, e.g. `scopes`
+    ///
+    /// This sets the `Impersonate-Extra-{key}` headers, and is only sent along with
+    /// an impersonated user, since the API server requires one.
+    pub fn extra(&self, key: &str, values: &[&str]) -> Kubernetes {
+        let mut kube = self.clone();
+        kube.low_level.impersonation.extra.insert(
+            key.to_owned(),
+            values.iter().map(|&v| v.to_owned()).collect(),
+        );
+        kube
+    }
+
+    /// Get a kubernetes client that retries transient failures according to `policy`
+    ///
+    /// ## Examples
+    ///
+    /// ```no_run
+    /// # use kubeclient::prelude::*;
+    /// # use kubeclient::RetryPolicy;
+    /// let kube = Kubernetes::load_conf("admin.conf")?;
+    /// let is_healthy = kube.retry_policy(RetryPolicy::none()).healthy()?;
+    /// ```
+    pub fn retry_policy(&self, policy: RetryPolicy) -> Kubernetes {
+        let mut kube = self.clone();
+        kube.low_level.retry = policy;
+        kube
+    }
+
+    /// Get a kubernetes client with its own rate limit
+    ///
+    /// This allows `qps` requests per second on average, with bursts of up to `burst` requests.
+    /// Unlike other clones, the returned client doesn't share its rate limit with this one.
+    /// A non-positive `qps` disables rate limiting.
+    ///
+    /// ## Examples
+    ///
+    /// ```no_run
+    /// # use kubeclient::prelude::*;
+    /// let kube = Kubernetes::load_conf("admin.conf")?;
+    /// let fast = kube.rate_limit(50.0, 100);
+    /// ```
+    pub fn rate_limit(&self, qps: f32, burst: u32) -> Kubernetes {
+        let mut kube = self.clone();
+        kube.low_level.rate_limiter = RateLimiter::new(qps, burst);
+        kube
     }
 
     /// Check to see if the Kubernetes API is healthy
@@ -189,7 +478,7 @@ impl Kubernetes {
     /// ```
     pub fn apply<P: AsRef<Path>>(&self, path: P) -> Result<()> {
         let _: Vec<Value> = self.low_level.each_resource_path(path, |path| {
-            self.low_level.apply_file(&path)
+            self.low_level.apply_file(&path, self.namespace_override())
                 .chain_err(|| format!("Failed to apply {}", path.display()))
         })?;
 
@@ -213,7 +502,7 @@ impl Kubernetes {
     /// ```
     pub fn replace<P: AsRef<Path>>(&self, path: P) -> Result<()> {
         let _: Vec<Value> = self.low_level.each_resource_path(path, |path| {
-            self.low_level.replace_file(&path)
+            self.low_level.replace_file(&path, self.namespace_override())
                 .chain_err(|| format!("Failed to replace {}", path.display()))
         })?;
 
@@ -238,7 +527,8 @@ impl Kubernetes {
     /// let response = kube.create(&secret)?;
     /// ```
     pub fn create<R: Resource>(&self, resource: &R) -> Result<R> {
-        let mut route = KindRoute::new(R::api(), R::kind().plural);
+        let api = R::api();
+        let mut route = KindRoute::new(&api, R::plural());
         if let Some(ns) = self.get_ns::<R>() {
             route.namespace(ns);
         }
@@ -251,7 +541,8 @@ impl Kubernetes {
     // is decidedly less ergonomic than `kube.deployments().exists("web-server")?`.
 
     fn exists<R: Resource>(&self, name: &str) -> Result<bool> {
-        let mut route = ResourceRoute::new(R::api(), R::kind().plural, name);
+        let api = R::api();
+        let mut route = ResourceRoute::new(&api, R::plural(), name);
         if let Some(ns) = self.get_ns::<R>() {
             route.namespace(ns);
         }
@@ -259,7 +550,8 @@ impl Kubernetes {
     }
 
     fn get<R: Resource>(&self, name: &str) -> Result<R> {
-        let mut route = ResourceRoute::new(R::api(), R::kind().plural, name);
+        let api = R::api();
+        let mut route = ResourceRoute::new(&api, R::plural(), name);
         if let Some(ns) = self.get_ns::<R>() {
             route.namespace(ns);
         }
@@ -267,7 +559,8 @@ impl Kubernetes {
     }
 
     fn list<R: ListableResource>(&self, query: Option<&ListQuery>) -> Result<Vec<R>> {
-        let mut route = KindRoute::new(R::api(), R::kind().plural);
+        let api = R::api();
+        let mut route = KindRoute::new(&api, R::plural());
         if let Some(ns) = self.get_ns::<R>() {
             route.namespace(ns);
         }
@@ -279,17 +572,73 @@ impl Kubernetes {
     }
 
     fn delete<R: Resource>(&self, name: &str) -> Result<()> {
-        let mut route = ResourceRoute::new(R::api(), R::kind().plural, name);
+        let api = R::api();
+        let mut route = ResourceRoute::new(&api, R::plural(), name);
         if let Some(ns) = self.get_ns::<R>() {
             route.namespace(ns);
         }
         self.low_level.delete(&route)
     }
 
-    fn get_ns<'a, R: Resource>(&'a self) -> Option<&'a str> {
-        match self.namespace {
-            Some(ref ns) => Some(ns),
-            None => R::default_namespace(),
+    fn patch<R: Resource>(&self, name: &str, patch: &Patch) -> Result<R> {
+        let api = R::api();
+        let mut route = ResourceRoute::new(&api, R::plural(), name);
+        if let Some(ns) = self.get_ns::<R>() {
+            route.namespace(ns);
+        }
+        self.low_level.patch(&route, patch)
+    }
+
+    // Uses the resource's namespace if set, like `kubectl replace`
+    fn replace_resource<R: Resource>(&self, resource: &R) -> Result<R> {
+        let metadata = resource.metadata();
+        let name = match metadata.name {
+            Some(ref name) => name,
+            None => bail!("must set metadata.name to replace kubernetes resource"),
+        };
+        let api = R::api();
+        let mut route = ResourceRoute::new(&api, R::plural(), name);
+        if let Some(ns) = self.get_ns::<R>() {
+            route.namespace(metadata.namespace.as_ref().map_or(ns, String::as_str));
         }
+        self.low_level.replace(&route, resource)
+    }
+
+    // The namespace to use for namespaced resources that don't specify one
+    fn namespace_override(&self) -> Option<&str> {
+        self.namespace.as_ref().or(self.default_namespace.as_ref()).map(String::as_str)
     }
-}
\ No newline at end of file
+
+    fn get_ns<'a, R: Resource>(&'a self) -> Option<&'a str> {
+        resource_namespace::<R>(self.namespace.as_ref(), self.default_namespace.as_ref())
+    }
+}
+
+/// The namespace to use for `R`, given a client's explicit and default namespaces
+pub(crate) fn resource_namespace<'a, R: Resource>(
+    namespace: Option<&'a String>,
+    default_namespace: Option<&'a String>,
+) -> Option<&'a str> {
+    kind_namespace(R::default_namespace(), namespace, default_namespace)
+}
+
+/// The namespace to use for a kind whose default namespace is `kind_default`,
+/// which is `None` for kinds that aren't namespaced
+pub(crate) fn kind_namespace<'a>(
+    kind_default: Option<&'a str>,
+    namespace: Option<&'a String>,
+    default_namespace: Option<&'a String>,
+) -> Option<&'a str> {
+    match namespace {
+        Some(ns) => Some(ns),
+        // Only namespaced kinds have a default namespace to override
+        None => kind_default.map(|default| {
+            default_namespace.map(String::as_str).unwrap_or(default)
+        }),
+    }
+}
+
+/// Whole milliseconds in `duration`
+pub(crate) fn duration_ms(duration: Duration) -> u64 {
+    duration.as_secs() * 1000 + u64::from(duration.subsec_millis())
+}
@@ -0,0 +1,85 @@
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Default requests per second, matching client-go
+pub(crate) const DEFAULT_QPS: f32 = 5.0;
+
+/// Default burst of requests above `DEFAULT_QPS`, matching client-go
+pub(crate) const DEFAULT_BURST: u32 = 10;
+
+/// Token bucket limiting the rate of requests to the API server
+///
+/// Clones share the same bucket, so all handles derived from
+/// one `Kubernetes` client are limited together.
+#[derive(Clone, Debug)]
+pub(crate) struct RateLimiter {
+    bucket: Option<Arc<Mutex<TokenBucket>>>,
+}
+
+#[derive(Debug)]
+struct TokenBucket {
+    qps: f64,
+    burst: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl Default for RateLimiter {
+    fn default() -> RateLimiter {
+        RateLimiter::new(DEFAULT_QPS, DEFAULT_BURST)
+    }
+}
+
+impl RateLimiter {
+    /// Allow `qps` requests per second on average, and bursts of up to `burst` requests
+    ///
+    /// A non-positive `qps` disables rate limiting.
+    pub(crate) fn new(qps: f32, burst: u32) -> RateLimiter {
+        if qps <= 0.0 || qps.is_nan() {
+            return RateLimiter::unlimited();
+        }
+        let burst = f64::from(burst.max(1));
+        let bucket = TokenBucket {
+            qps: f64::from(qps),
+            burst,
+            tokens: burst,
+            last_refill: Instant::now(),
+        };
+        RateLimiter { bucket: Some(Arc::new(Mutex::new(bucket))) }
+    }
+
+    pub(crate) fn unlimited() -> RateLimiter {
+        RateLimiter { bucket: None }
+    }
+
+    /// Blocks until a request may be sent
+    pub(crate) fn acquire(&self) {
+        if let Some(wait) = self.reserve() {
+            thread::sleep(wait);
+        }
+    }
+
+    /// Takes a token, returning how long to wait before the request may be sent
+    ///
+    /// The bucket may go into debt, so that waiting requests are served in order
+    /// without holding the lock while they wait.
+    pub(crate) fn reserve(&self) -> Option<Duration> {
+        let bucket = self.bucket.as_ref()?;
+        let mut bucket = bucket.lock().unwrap_or_else(|err| err.into_inner());
+
+        let now = Instant::now();
+        let elapsed = now - bucket.last_refill;
+        let elapsed = elapsed.as_secs() as f64 + f64::from(elapsed.subsec_nanos()) / 1e9;
+        bucket.tokens = (bucket.tokens + elapsed * bucket.qps).min(bucket.burst);
+        bucket.last_refill = now;
+        bucket.tokens -= 1.0;
+        if bucket.tokens >= 0.0 {
+            return None;
+        }
+
+        let wait = -bucket.tokens / bucket.qps;
+        let secs = wait.trunc();
+        Some(Duration::new(secs as u64, ((wait - secs) * 1e9) as u32))
+    }
+}
@@ -4,6 +4,8 @@ use errors::*;
 use std::marker::PhantomData;
 use super::ResourceRoute;
 
+/// How many times `update_with` tries to replace a resource before giving up on conflicts
+pub(crate) const MAX_UPDATE_ATTEMPTS: u32 = 5;
 
 pub struct KubeClient<R> {
     pub(super) kube: Kubernetes,
@@ -23,7 +25,8 @@ impl<R> KubeClient<R> {
 //     // https://github.com/kubernetes-incubator/client-python/issues/58
 //     pub fn exec(&self, pod_name: &str, exec: PodExec) -> Result<String> {
 //         let resource = format!("{}/exec", pod_name);
-//         let mut route = ResourceRoute::new(Pod::api(), Pod::kind().route(), &resource);
+//         let api = Pod::api();
+//         let mut route = ResourceRoute::new(&api, Pod::plural(), &resource);
 //         if let Some(ns) = self.kube.get_ns::<Pod>() {
 //             route.namespace(ns);
 //         }
@@ -49,7 +52,8 @@ impl KubeClient<Deployment> {
     /// ```
     pub fn scale(&self, deployment_name: &str, count: u32) -> Result<Scale> {
         let resource = format!("{}/scale", deployment_name);
-        let mut route = ResourceRoute::new(Deployment::api(), Deployment::kind().plural, &resource);
+        let api = Deployment::api();
+        let mut route = ResourceRoute::new(&api, Deployment::plural(), &resource);
         let ns = self.kube.get_ns::<Deployment>().expect("Namespace necessary for kubernetes scale operation");
         route.namespace(ns);
 
@@ -113,6 +117,70 @@ pub trait WriteClient {
     /// ```
     fn create(&self, resource: &Self::R) -> Result<Self::R>;
 
+    /// Replaces the resource with the same name
+    ///
+    /// This is similar to the `kubectl replace` CLI commands.
+    ///
+    /// If `metadata.resourceVersion` is set, the API server only replaces
+    /// the resource if it hasn't changed since that version, and fails
+    /// with a conflict otherwise (see `Error::is_conflict`).
+    ///
+    /// **Note**: most of the resource type defintions are incomplete
+    /// Fields they don't model at the top level are kept, but fields missing from
+    /// nested types like `spec` are dropped, so the API server resets them.
+    /// Use `patch` to change a resource without sending all of it.
+    ///
+    /// ## Examples
+    ///
+    /// ```no_run
+    /// # use kubeclient::prelude::*;
+    /// let kube = Kubernetes::load_conf("admin.conf")?;
+    /// let mut deployment = kube.deployments().get("web-server")?;
+    /// deployment.spec.replicas = Some(3);
+    /// kube.deployments().replace(&deployment)?;
+    /// ```
+    fn replace(&self, resource: &Self::R) -> Result<Self::R>;
+
+    /// Updates the named resource by getting it, calling `update` on it, then replacing it
+    ///
+    /// If the resource is modified by someone else before it's replaced, this
+    /// starts over with the latest version, so `update` may be called more than once.
+    /// This gives up after a few conflicts, returning the conflict error.
+    ///
+    /// **Note**: most of the resource type defintions are incomplete
+    /// Fields they don't model at the top level are kept, but fields missing from
+    /// nested types like `spec` are dropped, so the API server resets them.
+    /// Use `patch` to change a resource without sending all of it.
+    ///
+    /// ## Examples
+    ///
+    /// ```no_run
+    /// # use kubeclient::prelude::*;
+    /// let kube = Kubernetes::load_conf("admin.conf")?;
+    /// kube.deployments().update_with("web-server", |deployment| {
+    ///     deployment.spec.replicas = Some(3);
+    /// })?;
+    /// ```
+    fn update_with<F>(&self, name: &str, update: F) -> Result<Self::R>
+    where F: FnMut(&mut Self::R);
+
+    /// Patches the named resource, returning the updated resource
+    ///
+    /// This is similar to the `kubectl patch` CLI commands.
+    ///
+    /// ## Examples
+    ///
+    /// ```no_run
+    /// # #[macro_use] extern crate serde_json;
+    /// # extern crate kubeclient;
+    /// # use kubeclient::prelude::*;
+    /// # use kubeclient::resources::Patch;
+    /// let kube = Kubernetes::load_conf("admin.conf")?;
+    /// let patch = Patch::Strategic(json!({ "metadata": { "labels": { "tier": "frontend" } } }));
+    /// let deployment = kube.deployments().patch("web-server", patch)?;
+    /// ```
+    fn patch(&self, name: &str, patch: Patch) -> Result<Self::R>;
+
     /// Deleteds the named resource
     ///
     /// This is similar to the `kubectl delete` CLI commands.
@@ -173,6 +241,28 @@ impl<R: Resource> WriteClient for KubeClient<R> {
         self.kube.create(resource)
     }
 
+    fn replace(&self, resource: &Self::R) -> Result<Self::R> {
+        self.kube.replace_resource(resource)
+    }
+
+    fn update_with<F>(&self, name: &str, mut update: F) -> Result<Self::R>
+    where F: FnMut(&mut Self::R)
+    {
+        let mut attempt = 1;
+        loop {
+            let mut resource = self.kube.get::<Self::R>(name)?;
+            update(&mut resource);
+            match self.replace(&resource) {
+                Err(ref err) if err.is_conflict() && attempt < MAX_UPDATE_ATTEMPTS => attempt += 1,
+                result => return result,
+            }
+        }
+    }
+
+    fn patch(&self, name: &str, patch: Patch) -> Result<Self::R> {
+        self.kube.patch(name, &patch)
+    }
+
     fn delete(&self, name: &str) -> Result<()> {
         self.kube.delete::<Self::R>(name)
     }
@@ -0,0 +1,137 @@
+use chrono::{DateTime, Utc};
+use rand::{self, Rng};
+use reqwest::{header, Method, StatusCode};
+use reqwest::header::HeaderMap;
+use std::cmp;
+use std::time::Duration;
+use super::duration_ms;
+use errors::*;
+
+/// Policy for retrying requests that failed for transient reasons
+///
+/// A request is retried when the API server is throttling it (`429 Too Many Requests`),
+/// and, for idempotent verbs (`GET`, `HEAD`, `PUT`, `DELETE`), when the server is
+/// unavailable (`502`, `503`, `504`) or the connection failed. Retries wait for the
+/// `Retry-After` header if the server sent one, up to the maximum backoff, or else back
+/// off exponentially with jitter.
+///
+/// The default policy makes at most 3 attempts, backing off from 200ms up to 10s.
+///
+/// ## Examples
+///
+/// ```no_run
+/// # use kubeclient::prelude::*;
+/// # use kubeclient::RetryPolicy;
+/// # use std::time::Duration;
+/// let kube = Kubernetes::load_conf("admin.conf")?
+///     .retry_policy(RetryPolicy::new(5).max_backoff(Duration::from_secs(30)));
+/// ```
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> RetryPolicy {
+        RetryPolicy::new(3)
+    }
+}
+
+impl RetryPolicy {
+    /// Make up to `max_attempts` attempts for each request, including the first
+    pub fn new(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: cmp::max(max_attempts, 1),
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(10),
+        }
+    }
+
+    /// Never retry requests
+    pub fn none() -> RetryPolicy {
+        RetryPolicy::new(1)
+    }
+
+    /// Set the backoff before the first retry, which doubles for each further retry
+    pub fn initial_backoff(mut self, backoff: Duration) -> RetryPolicy {
+        self.initial_backoff = backoff;
+        self
+    }
+
+    /// Set the longest backoff between retries
+    ///
+    /// This also limits how long a `Retry-After` header can make a retry wait.
+    pub fn max_backoff(mut self, backoff: Duration) -> RetryPolicy {
+        self.max_backoff = backoff;
+        self
+    }
+
+    /// Returns how long to wait before retrying after the `attempt`-th attempt,
+    /// or `None` if the request should not be retried
+    ///
+    /// `outcome` is either the status and headers of the response, or the error sending the request.
+    pub(crate) fn retry_delay(
+        &self,
+        method: &Method,
+        outcome: ::std::result::Result<(StatusCode, &HeaderMap), &Error>,
+        attempt: u32,
+    ) -> Option<Duration> {
+        if attempt >= self.max_attempts {
+            return None;
+        }
+
+        let retryable = match outcome {
+            Ok((status, _)) => match status {
+                StatusCode::TOO_MANY_REQUESTS => true,
+                StatusCode::BAD_GATEWAY |
+                StatusCode::SERVICE_UNAVAILABLE |
+                StatusCode::GATEWAY_TIMEOUT => is_idempotent(method),
+                _ => false,
+            },
+            Err(err) => is_idempotent(method) && is_transient(err),
+        };
+        if !retryable {
+            return None;
+        }
+
+        let retry_after = outcome.ok().and_then(|(_, headers)| retry_after(headers));
+        match retry_after {
+            Some(delay) => Some(cmp::min(delay, self.max_backoff)),
+            None => Some(self.backoff(attempt)),
+        }
+    }
+
+    /// Exponential backoff with "equal jitter": a random delay between half and all of the backoff
+    fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 1u32.checked_shl(attempt - 1).unwrap_or(u32::MAX);
+        let backoff = self.initial_backoff.checked_mul(factor).unwrap_or(self.max_backoff);
+        let backoff = cmp::min(backoff, self.max_backoff);
+        let half = backoff / 2;
+        let jitter_ms = rand::thread_rng().gen_range(0, duration_ms(half) + 1);
+        half + Duration::from_millis(jitter_ms)
+    }
+}
+
+fn is_idempotent(method: &Method) -> bool {
+    matches!(*method, Method::GET | Method::HEAD | Method::PUT | Method::DELETE | Method::OPTIONS)
+}
+
+fn is_transient(err: &Error) -> bool {
+    match *err.kind() {
+        ErrorKind::Http(ref err) => err.is_http() || err.is_timeout(),
+        ErrorKind::Io(_) => true,
+        _ => false,
+    }
+}
+
+/// Parses `Retry-After` as either delay-seconds or an HTTP-date
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(header::RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = DateTime::parse_from_rfc2822(value).ok()?.with_timezone(&Utc);
+    Some((date - Utc::now()).to_std().unwrap_or(Duration::from_secs(0)))
+}
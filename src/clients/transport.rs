@@ -0,0 +1,233 @@
+use http::{Request, Response};
+use reqwest;
+use std::io::Read;
+use url::Url;
+use errors::*;
+
+/// Sends HTTP requests to the API server for a `Kubernetes` client
+///
+/// The client prepares each request, including credentials, impersonation,
+/// and user agent headers, and handles retries, rate limiting, and decoding
+/// responses. The transport only has to deliver the request.
+///
+/// The default is [`ReqwestTransport`](struct.ReqwestTransport.html). Another transport
+/// can be set with [`KubernetesBuilder::transport`](struct.KubernetesBuilder.html#method.transport),
+/// e.g. to return canned responses in tests, record traffic, or use another TLS stack.
+///
+/// ## Examples
+///
+/// ```no_run
+/// # extern crate http;
+/// # extern crate kubeclient;
+/// use http::{Request, Response};
+/// use kubeclient::{KubernetesBuilder, Transport};
+/// use kubeclient::errors::Result;
+///
+/// struct Healthy;
+///
+/// impl Transport for Healthy {
+///     fn send(&self, _request: Request<Vec<u8>>) -> Result<Response<Vec<u8>>> {
+///         Ok(Response::new(b"ok".to_vec()))
+///     }
+/// }
+///
+/// let kube = KubernetesBuilder::new("http://fake-cluster")?
+///     .transport(Healthy)
+///     .build()?;
+/// assert!(kube.healthy()?);
+/// ```
+pub trait Transport: Send + Sync {
+    /// Sends `request` and returns its response
+    ///
+    /// Unsuccessful HTTP statuses should be returned as responses.
+    /// Errors are for failing to get a response at all, and those
+    /// of kind `Http` or `Io` are considered transient by the `RetryPolicy`.
+    fn send(&self, request: Request<Vec<u8>>) -> Result<Response<Vec<u8>>>;
+}
+
+/// The default `Transport`, sending requests with a blocking `reqwest::Client`
+///
+/// When built from a kubeconfig, the client is configured with the cluster's
+/// TLS settings, client certificate, proxy, and the builder's timeout.
+pub struct ReqwestTransport {
+    client: reqwest::Client,
+}
+
+impl ReqwestTransport {
+    /// Send requests with `client`
+    pub fn new(client: reqwest::Client) -> ReqwestTransport {
+        ReqwestTransport { client }
+    }
+}
+
+impl Transport for ReqwestTransport {
+    fn send(&self, request: Request<Vec<u8>>) -> Result<Response<Vec<u8>>> {
+        let (parts, body) = request.into_parts();
+        let url = Url::parse(&parts.uri.to_string())?;
+
+        let mut req = self.client.request(parts.method, url).headers(parts.headers);
+        if !body.is_empty() {
+            req = req.body(body);
+        }
+        let mut response = req.send()?;
+
+        let mut body = Vec::new();
+        response.read_to_end(&mut body)?;
+
+        let mut output = Response::new(body);
+        *output.status_mut() = response.status();
+        *output.headers_mut() = response.headers().clone();
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clients::{KubernetesBuilder, ReadClient, RetryPolicy, WriteClient};
+    use clients::resource_clients::MAX_UPDATE_ATTEMPTS;
+    use http::StatusCode;
+    use resources::Resource;
+    use serde_json::Value;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    // A canned response's status code, content type, and body
+    type CannedResponse = (u16, &'static str, String);
+
+    fn json(status: u16, body: &str) -> CannedResponse {
+        (status, "application/json", body.to_owned())
+    }
+
+    // Replies with canned responses in order, and records the requests it was sent
+    #[derive(Clone, Default)]
+    struct Canned {
+        responses: Arc<Mutex<Vec<CannedResponse>>>,
+        requests: Arc<Mutex<Vec<String>>>,
+        bodies: Arc<Mutex<Vec<Vec<u8>>>>,
+    }
+
+    impl Canned {
+        fn new(responses: Vec<CannedResponse>) -> Canned {
+            Canned { responses: Arc::new(Mutex::new(responses)), ..Canned::default() }
+        }
+
+        fn requests(&self) -> Vec<String> {
+            self.requests.lock().unwrap().clone()
+        }
+
+        fn bodies(&self) -> Vec<Vec<u8>> {
+            self.bodies.lock().unwrap().clone()
+        }
+    }
+
+    impl Transport for Canned {
+        fn send(&self, request: Request<Vec<u8>>) -> Result<Response<Vec<u8>>> {
+            self.requests.lock().unwrap().push(format!("{} {}", request.method(), request.uri()));
+            self.bodies.lock().unwrap().push(request.body().clone());
+            let (status, content_type, body) = self.responses.lock().unwrap().remove(0);
+            let mut response = Response::new(body.into_bytes());
+            *response.status_mut() = StatusCode::from_u16(status).unwrap();
+            response.headers_mut().insert("content-type", content_type.parse().unwrap());
+            Ok(response)
+        }
+    }
+
+    fn kube(transport: &Canned) -> ::clients::Kubernetes {
+        KubernetesBuilder::new("https://fake-cluster").unwrap()
+            .namespace("default")
+            .retry_policy(RetryPolicy::new(3).initial_backoff(Duration::from_millis(1)))
+            .no_rate_limit()
+            .transport(transport.clone())
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn requests_go_through_the_transport_and_are_retried() {
+        let transport = Canned::new(vec![
+            json(503, "{}"),
+            json(200, r#"{"metadata": {"name": "web"}, "data": {}}"#),
+        ]);
+        let config_map = kube(&transport).config_maps().get("web").unwrap();
+
+        assert_eq!(config_map.metadata().name.as_deref(), Some("web"));
+        let get = "GET https://fake-cluster/api/v1/namespaces/default/configmaps/web";
+        assert_eq!(transport.requests(), vec![get, get]);
+    }
+
+    #[test]
+    fn status_responses_become_api_errors() {
+        let transport = Canned::new(vec![
+            json(404, r#"{"kind": "Status", "status": "Failure", "message": "configmaps \"web\" not found", "reason": "NotFound", "code": 404}"#),
+        ]);
+        let err = kube(&transport).config_maps().get("web").unwrap_err();
+
+        assert!(err.is_not_found());
+        assert_eq!(err.status().unwrap().message, "configmaps \"web\" not found");
+        assert_eq!(transport.requests().len(), 1);
+    }
+
+    #[test]
+    fn other_error_responses_keep_the_start_of_the_body() {
+        let page = format!("<html><body>{}</body></html>", "Bad Gateway ".repeat(200));
+        let transport = Canned::new(vec![(502, "text/html", page.clone()); 3]);
+        let err = kube(&transport).config_maps().get("web").unwrap_err();
+
+        assert_eq!(err.status_code(), Some(502));
+        assert!(err.status().is_none());
+        match *err.kind() {
+            ErrorKind::UnexpectedResponse(_, ref content_type, ref body) => {
+                assert_eq!(content_type, "text/html");
+                assert_eq!(body.len(), 1024 + 3);
+                assert!(page.starts_with(&body[..1024]));
+                assert!(body.ends_with("..."));
+            }
+            ref kind => panic!("unexpected error kind: {:?}", kind),
+        }
+        // Retried like any other 502 for an idempotent request
+        assert_eq!(transport.requests().len(), 3);
+    }
+
+    const CONFIG_MAP: &str = r#"{"metadata": {"name": "web", "resourceVersion": "1"}, "data": {}, "binaryData": {"logo": "AA=="}}"#;
+    const CONFLICT: &str = r#"{"kind": "Status", "status": "Failure", "reason": "Conflict", "code": 409}"#;
+
+    #[test]
+    fn update_with_starts_over_after_a_conflict() {
+        let transport = Canned::new(vec![
+            json(200, CONFIG_MAP),
+            json(409, CONFLICT),
+            json(200, CONFIG_MAP),
+            json(200, CONFIG_MAP),
+        ]);
+        let mut updates = 0;
+        kube(&transport).config_maps().update_with("web", |config_map| {
+            config_map.insert("environment", "production");
+            updates += 1;
+        }).unwrap();
+
+        assert_eq!(updates, 2);
+        let url = "https://fake-cluster/api/v1/namespaces/default/configmaps/web";
+        let expected: Vec<_> = ["GET", "PUT", "GET", "PUT"].iter().map(|m| format!("{} {}", m, url)).collect();
+        assert_eq!(transport.requests(), expected);
+
+        // Fields that `ConfigMap` doesn't model are sent back unchanged
+        let sent: Value = ::serde_json::from_slice(&transport.bodies()[3]).unwrap();
+        assert_eq!(sent["binaryData"]["logo"], "AA==");
+        assert_eq!(sent["data"]["environment"], "production");
+    }
+
+    #[test]
+    fn update_with_gives_up_after_repeated_conflicts() {
+        let mut responses = Vec::new();
+        for _ in 0..MAX_UPDATE_ATTEMPTS {
+            responses.push(json(200, CONFIG_MAP));
+            responses.push(json(409, CONFLICT));
+        }
+        let transport = Canned::new(responses);
+        let err = kube(&transport).config_maps().update_with("web", |_| ()).unwrap_err();
+
+        assert!(err.is_conflict());
+        assert_eq!(transport.requests().len(), 2 * MAX_UPDATE_ATTEMPTS as usize);
+    }
+}
@@ -3,6 +3,7 @@
 // Lifted from https://github.com/camallo/k8s-client-rs/blob/master/src/kubeconfig.rs
 // until a more complete kubernetes client exists
 
+use std::collections::BTreeMap;
 use std::env;
 use std::fs::File;
 use std::io::prelude::*;
@@ -10,74 +11,231 @@ use std::path::{Path, PathBuf};
 use serde_yaml;
 use openssl::x509::X509;
 use openssl::pkey::{PKey, Private};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::de::Error as DeError;
 use url::Url;
-use url_serde;
 use base64;
 use errors::*;
 
+/// Directory where kubernetes mounts the service account credentials in a pod
+pub const SERVICE_ACCOUNT_DIR: &str = "/var/run/secrets/kubernetes.io/serviceaccount";
+
 /// Configuration to build a Kubernetes client.
-#[derive(Debug, Serialize, Deserialize)]
+///
+/// Fields that aren't modeled here are preserved, so a config can be
+/// loaded, edited, and saved without losing any information.
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct KubeConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub kind: Option<String>,
-    #[serde(rename = "apiVersion")]
+    #[serde(rename = "apiVersion", skip_serializing_if = "Option::is_none")]
     pub api_version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub preferences: Option<Preferences>,
+    #[serde(default)]
     pub clusters: Vec<NamedCluster>,
+    #[serde(default)]
     pub users: Vec<NamedAuthInfo>,
+    #[serde(default)]
     pub contexts: Vec<NamedContext>,
-    #[serde(rename = "current-context")]
+    #[serde(rename = "current-context", default)]
     pub current_context: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub extensions: Option<Vec<NamedExtension>>,
+    #[serde(flatten)]
+    other: BTreeMap<String, serde_yaml::Value>,
+    /// The files this config was loaded from
+    #[serde(skip)]
+    paths: Vec<PathBuf>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct Preferences {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub colors: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub extensions: Option<Vec<NamedExtension>>,
+    #[serde(flatten)]
+    other: BTreeMap<String, serde_yaml::Value>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct NamedCluster {
     pub name: String,
     pub cluster: Cluster,
+    #[serde(flatten)]
+    other: BTreeMap<String, serde_yaml::Value>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(from = "ClusterFields", into = "ClusterFields")]
 pub struct Cluster {
-    #[serde(with = "url_serde")]
     pub server: Url,
-    #[serde(rename = "insecure-skip-tls-verify")]
+    #[serde(rename = "insecure-skip-tls-verify", skip_serializing_if = "Option::is_none")]
     pub insecure_tls: Option<bool>,
-    #[serde(rename = "certificate-authority")]
+    /// Server name to verify the API server's certificate against, instead of the `server` host
+    ///
+    /// This is not supported: the TLS stack used by this crate can't send another SNI name
+    /// or verify against one, so the certificate is still verified against the `server`
+    /// host, and a warning is logged. It is still preserved when saving.
+    #[serde(rename = "tls-server-name", skip_serializing_if = "Option::is_none")]
+    pub tls_server_name: Option<String>,
+    #[serde(rename = "certificate-authority", skip_serializing_if = "Option::is_none")]
+    pub ca_file: Option<String>,
+    /// Base64 encoded PEM of the CA cert
+    #[serde(rename = "certificate-authority-data", skip_serializing_if = "Option::is_none")]
+    pub ca_data: Option<String>,
+    /// URL of an HTTP(S) or SOCKS5 proxy to reach the API server through
+    ///
+    /// When unset, the proxy is taken from the `HTTPS_PROXY`/`HTTP_PROXY`
+    /// environment variables, excluding hosts listed in `NO_PROXY`.
+    /// SOCKS5 proxies require the `socks` cargo feature.
+    #[serde(rename = "proxy-url", skip_serializing_if = "Option::is_none")]
+    pub proxy_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extensions: Option<Vec<NamedExtension>>,
+    #[serde(flatten)]
+    other: BTreeMap<String, serde_yaml::Value>,
+    /// Directory of the kubeconfig this was loaded from, for resolving relative paths
+    #[serde(skip)]
+    base_dir: Option<PathBuf>,
+    /// `server` as written in the kubeconfig, since `Url` normalizes it, e.g. adding a trailing `/`
+    #[serde(skip)]
+    server_source: Option<String>,
+}
+
+// How a `Cluster` is (de)serialized, so that `server` is saved as it was written
+#[derive(Serialize, Deserialize)]
+struct ClusterFields {
+    server: ServerUrl,
+    #[serde(rename = "insecure-skip-tls-verify", skip_serializing_if = "Option::is_none")]
+    insecure_tls: Option<bool>,
+    #[serde(rename = "tls-server-name", skip_serializing_if = "Option::is_none")]
+    tls_server_name: Option<String>,
+    #[serde(rename = "certificate-authority", skip_serializing_if = "Option::is_none")]
     ca_file: Option<String>,
-    #[serde(rename = "certificate-authority-data")]
+    #[serde(rename = "certificate-authority-data", skip_serializing_if = "Option::is_none")]
     ca_data: Option<String>,
-    pub extensions: Option<Vec<NamedExtension>>,
+    #[serde(rename = "proxy-url", skip_serializing_if = "Option::is_none")]
+    proxy_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    extensions: Option<Vec<NamedExtension>>,
+    #[serde(flatten)]
+    other: BTreeMap<String, serde_yaml::Value>,
+}
+
+struct ServerUrl {
+    url: Url,
+    source: Option<String>,
+}
+
+impl Serialize for ServerUrl {
+    fn serialize<S: Serializer>(&self, serializer: S) -> ::std::result::Result<S::Ok, S::Error> {
+        match self.source {
+            // Unless `server` was changed since it was loaded
+            Some(ref source) if Url::parse(source).ok().as_ref() == Some(&self.url) => serializer.serialize_str(source),
+            _ => serializer.serialize_str(self.url.as_str()),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ServerUrl {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> ::std::result::Result<ServerUrl, D::Error> {
+        let source = String::deserialize(deserializer)?;
+        let url = Url::parse(&source).map_err(|err| D::Error::custom(format!("invalid server URL '{}': {}", source, err)))?;
+        Ok(ServerUrl { url, source: Some(source) })
+    }
+}
+
+impl From<ClusterFields> for Cluster {
+    fn from(fields: ClusterFields) -> Cluster {
+        Cluster {
+            server: fields.server.url,
+            insecure_tls: fields.insecure_tls,
+            tls_server_name: fields.tls_server_name,
+            ca_file: fields.ca_file,
+            ca_data: fields.ca_data,
+            proxy_url: fields.proxy_url,
+            extensions: fields.extensions,
+            other: fields.other,
+            base_dir: None,
+            server_source: fields.server.source,
+        }
+    }
+}
+
+impl From<Cluster> for ClusterFields {
+    fn from(cluster: Cluster) -> ClusterFields {
+        ClusterFields {
+            server: ServerUrl { url: cluster.server, source: cluster.server_source },
+            insecure_tls: cluster.insecure_tls,
+            tls_server_name: cluster.tls_server_name,
+            ca_file: cluster.ca_file,
+            ca_data: cluster.ca_data,
+            proxy_url: cluster.proxy_url,
+            extensions: cluster.extensions,
+            other: cluster.other,
+        }
+    }
 }
 
 /// Given two Option<String> parameters representing
 /// base64 encoded data, or file name with the data
 /// return the data form the first found in the specified order.
-fn get_from_b64data_or_file(data: &Option<String>, file: &Option<String>) -> Option<String> {
+///
+/// `field` is the kubeconfig field name of the file, used in error messages.
+pub(crate) fn get_from_b64data_or_file(data: &Option<String>, file: Option<PathBuf>, field: &str) -> Result<Option<String>> {
     if let &Some(ref data) = data {
-        let decoded = base64::decode(&data).expect("Unable to decode base64.");
-        Some(String::from_utf8(decoded).expect("Unable to convert to string."))
-    } else if let &Some(ref file) = file {
+        let decoded = base64::decode(&data)
+            .chain_err(|| format!("Unable to decode {}-data as base64", field))?;
+        let data = String::from_utf8(decoded)
+            .chain_err(|| format!("{}-data is not UTF-8", field))?;
+        Ok(Some(data))
+    } else if let Some(file) = file {
         let mut data = String::new();
-        let mut f = File::open(file).expect("Unable to open file.");
-        f.read_to_string(&mut data)
-            .expect("File data is not UTF-8.");
-        Some(data)
+        File::open(&file)
+            .and_then(|mut f| f.read_to_string(&mut data))
+            .chain_err(|| format!("Unable to read {} file {}", field, file.display()))?;
+        Ok(Some(data))
     } else {
-        None
+        Ok(None)
+    }
+}
+
+/// Resolves a path from a kubeconfig relative to the kubeconfig's directory, like `kubectl`
+pub(crate) fn resolve_path(base_dir: &Option<PathBuf>, file: &str) -> PathBuf {
+    match *base_dir {
+        Some(ref dir) if Path::new(file).is_relative() => dir.join(file),
+        _ => PathBuf::from(file),
     }
 }
 
 impl Cluster {
-    pub fn ca_cert(&self) -> Option<X509> {
-        get_from_b64data_or_file(&self.ca_data, &self.ca_file).map(|k| {
-            X509::from_pem(k.as_ref()).expect("Invalid kubeconfig - ca cert is not PEM-encoded")
-        })
+    pub fn new(server: Url) -> Cluster {
+        Cluster {
+            server,
+            insecure_tls: None,
+            tls_server_name: None,
+            ca_file: None,
+            ca_data: None,
+            proxy_url: None,
+            extensions: None,
+            other: BTreeMap::new(),
+            base_dir: None,
+            server_source: None,
+        }
+    }
+
+    pub fn ca_cert(&self) -> Result<Option<X509>> {
+        let ca_file = self.ca_file.as_ref().map(|f| resolve_path(&self.base_dir, f));
+        match get_from_b64data_or_file(&self.ca_data, ca_file, "certificate-authority")? {
+            Some(pem) => {
+                let cert = X509::from_pem(pem.as_ref())
+                    .chain_err(|| "certificate-authority is not a PEM-encoded certificate")?;
+                Ok(Some(cert))
+            }
+            None => Ok(None),
+        }
     }
 }
 
@@ -85,37 +243,131 @@ impl Cluster {
 pub struct NamedAuthInfo {
     pub name: String,
     pub user: AuthInfo,
+    #[serde(flatten)]
+    other: BTreeMap<String, serde_yaml::Value>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct AuthInfo {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub password: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub token: Option<String>,
-    #[serde(rename = "tokenFile")]
+    #[serde(rename = "tokenFile", skip_serializing_if = "Option::is_none")]
     pub token_file: Option<String>,
-    #[serde(rename = "client-certificate")]
-    client_certificate_file: Option<String>,
-    #[serde(rename = "client-certificate-data")]
-    client_certificate_data: Option<String>,
-    #[serde(rename = "client-key")]
+    #[serde(rename = "client-certificate", skip_serializing_if = "Option::is_none")]
+    pub client_certificate_file: Option<String>,
+    /// Base64 encoded PEM of the client cert
+    #[serde(rename = "client-certificate-data", skip_serializing_if = "Option::is_none")]
+    pub client_certificate_data: Option<String>,
+    #[serde(rename = "client-key", skip_serializing_if = "Option::is_none")]
     pub client_key_file: Option<String>,
-    #[serde(rename = "client-key-data")]
+    /// Base64 encoded PEM of the client key
+    #[serde(rename = "client-key-data", skip_serializing_if = "Option::is_none")]
     pub client_key_data: Option<String>,
+    #[serde(rename = "as", skip_serializing_if = "Option::is_none")]
     pub impersonate: Option<String>,
-    //TODO
+    #[serde(rename = "as-groups", skip_serializing_if = "Option::is_none")]
+    pub impersonate_groups: Option<Vec<String>>,
+    #[serde(rename = "as-user-extra", skip_serializing_if = "Option::is_none")]
+    pub impersonate_extra: Option<BTreeMap<String, Vec<String>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exec: Option<ExecConfig>,
+    #[serde(rename = "auth-provider", skip_serializing_if = "Option::is_none")]
+    pub auth_provider: Option<AuthProviderConfig>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extensions: Option<Vec<NamedExtension>>,
+    #[serde(flatten)]
+    other: BTreeMap<String, serde_yaml::Value>,
+    /// Directory of the kubeconfig this was loaded from, for resolving relative paths
+    #[serde(skip)]
+    base_dir: Option<PathBuf>,
+}
+
+/// Configuration for an authentication provider, e.g. `oidc`
+///
+/// For `oidc`, the `config` map holds the `id-token` and `refresh-token`,
+/// along with the `idp-issuer-url`, `client-id`, and `client-secret`
+/// used to refresh the `id-token` when it expires.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AuthProviderConfig {
+    pub name: String,
+    #[serde(default)]
+    pub config: BTreeMap<String, String>,
+}
+
+/// Configuration for an exec credential plugin, e.g. `aws eks get-token`
+///
+/// The command is run to obtain an `ExecCredential` containing a bearer token
+/// and/or a client certificate to authenticate with.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExecConfig {
+    #[serde(rename = "apiVersion", skip_serializing_if = "Option::is_none")]
+    pub api_version: Option<String>,
+    pub command: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub args: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub env: Option<Vec<ExecEnvVar>>,
+    #[serde(flatten)]
+    other: BTreeMap<String, serde_yaml::Value>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExecEnvVar {
+    pub name: String,
+    pub value: String,
 }
 
 impl AuthInfo {
-    pub fn client_certificate(&self) -> Option<X509> {
-        get_from_b64data_or_file(&self.client_certificate_data, &self.client_certificate_file)
-            .map(|k| X509::from_pem(k.as_ref())
-                .expect("Invalid kubeconfig - client cert is not PEM-encoded"))
+    pub fn client_certificate(&self) -> Result<Option<X509>> {
+        let file = self.client_certificate_file.as_ref().map(|f| resolve_path(&self.base_dir, f));
+        match get_from_b64data_or_file(&self.client_certificate_data, file, "client-certificate")? {
+            Some(pem) => {
+                let cert = X509::from_pem(pem.as_ref())
+                    .chain_err(|| "client-certificate is not a PEM-encoded certificate")?;
+                Ok(Some(cert))
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn client_key(&self) -> Result<Option<PKey<Private>>> {
+        let file = self.client_key_file.as_ref().map(|f| resolve_path(&self.base_dir, f));
+        match get_from_b64data_or_file(&self.client_key_data, file, "client-key")? {
+            Some(pem) => {
+                let key = PKey::private_key_from_pem(pem.as_ref())
+                    .chain_err(|| "client-key is not a PEM-encoded private key")?;
+                Ok(Some(key))
+            }
+            None => Ok(None),
+        }
     }
-    pub fn client_key(&self) -> Option<PKey<Private>> {
-        get_from_b64data_or_file(&self.client_key_data, &self.client_key_file)
-            .map(|k| PKey::private_key_from_pem(k.as_ref())
-                .expect("Invalid kubeconfig - client key is not PEM-encoded"))
+
+    /// The directory of the kubeconfig this was loaded from, if any
+    pub(crate) fn base_dir(&self) -> Option<PathBuf> {
+        self.base_dir.clone()
+    }
+
+    /// The path of the `tokenFile`, resolved relative to the kubeconfig's directory
+    pub fn token_file_path(&self) -> Option<PathBuf> {
+        self.token_file.as_ref().map(|f| resolve_path(&self.base_dir, f))
+    }
+
+    /// The exec plugin command, resolved relative to the kubeconfig's directory
+    ///
+    /// Like `kubectl`, only commands containing a path separator are resolved,
+    /// so that bare command names are still looked up on `$PATH`.
+    pub fn exec_command_path(&self) -> Option<PathBuf> {
+        self.exec.as_ref().map(|exec| {
+            if exec.command.contains('/') || exec.command.contains('\\') {
+                resolve_path(&self.base_dir, &exec.command)
+            } else {
+                PathBuf::from(&exec.command)
+            }
+        })
     }
 }
 
@@ -123,25 +375,39 @@ impl AuthInfo {
 pub struct NamedContext {
     pub name: String,
     pub context: Context,
+    #[serde(flatten)]
+    other: BTreeMap<String, serde_yaml::Value>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Context {
     pub cluster: String,
     pub user: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub namespace: Option<String>,
-    pub extensions: Option<Vec<Extension>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extensions: Option<Vec<NamedExtension>>,
+    #[serde(flatten)]
+    other: BTreeMap<String, serde_yaml::Value>,
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct NamedExtension {
-    pub name: String,
-    pub extension: Extension,
+impl Context {
+    pub fn new(cluster: &str, user: &str) -> Context {
+        Context {
+            cluster: cluster.to_owned(),
+            user: user.to_owned(),
+            namespace: None,
+            extensions: None,
+            other: BTreeMap::new(),
+        }
+    }
 }
 
+/// An extension entry, whose contents are arbitrary and defined by the extension
 #[derive(Clone, Debug, Serialize, Deserialize)]
-pub struct Extension {
-    pub extension: String,
+pub struct NamedExtension {
+    pub name: String,
+    pub extension: serde_yaml::Value,
 }
 
 #[derive(Clone, Debug)]
@@ -150,13 +416,179 @@ pub struct ClusterContext {
     pub cluster: Cluster,
     pub user: AuthInfo,
     pub namespace: Option<String>,
-    pub extensions: Option<Vec<Extension>>,
+    pub extensions: Option<Vec<NamedExtension>>,
+    /// The name of the `clusters` entry that `cluster` was read from
+    pub cluster_name: String,
+    /// The name of the `users` entry that `user` was read from
+    pub user_name: String,
+    /// The kubeconfig files this context was loaded from
+    ///
+    /// Refreshed `auth-provider` tokens are written back to these files, like `kubectl`,
+    /// unless this is empty (see `KubernetesBuilder::no_persist_tokens`).
+    /// Failing to write them is logged and otherwise ignored.
+    pub config_paths: Vec<PathBuf>,
+}
+
+impl ClusterContext {
+    /// Builds a context from the service account credentials mounted into a pod
+    ///
+    /// The API server address is read from the `KUBERNETES_SERVICE_HOST` and
+    /// `KUBERNETES_SERVICE_PORT` environment variables, and the `token`, `ca.crt`,
+    /// and `namespace` files are read from `SERVICE_ACCOUNT_DIR`.
+    /// The token is periodically re-read, so rotated tokens are picked up.
+    pub fn in_cluster() -> Result<ClusterContext> {
+        ClusterContext::from_service_account(SERVICE_ACCOUNT_DIR)
+    }
+
+    /// Builds an in-cluster context reading the service account credentials from `dir`
+    ///
+    /// This is the same as `in_cluster`, but allows overriding the directory
+    /// that the service account `token`, `ca.crt`, and `namespace` are read from.
+    pub fn from_service_account<P: AsRef<Path>>(dir: P) -> Result<ClusterContext> {
+        let host = env::var("KUBERNETES_SERVICE_HOST")
+            .chain_err(|| "Unable to read KUBERNETES_SERVICE_HOST")?;
+        let port = env::var("KUBERNETES_SERVICE_PORT")
+            .chain_err(|| "Unable to read KUBERNETES_SERVICE_PORT")?;
+        let server = if host.contains(':') {
+            Url::parse(&format!("https://[{}]:{}", host, port))?
+        } else {
+            Url::parse(&format!("https://{}:{}", host, port))?
+        };
+
+        let dir = dir.as_ref();
+        let mut namespace = String::new();
+        File::open(dir.join("namespace"))
+            .and_then(|mut f| f.read_to_string(&mut namespace))
+            .chain_err(|| format!("Unable to read service account namespace from {}", dir.display()))?;
+
+        let mut cluster = Cluster::new(server);
+        cluster.ca_file = Some(dir.join("ca.crt").to_string_lossy().into_owned());
+        // Projected service account tokens are rotated on disk, so use the
+        // token file rather than reading the token once.
+        let user = AuthInfo {
+            token_file: Some(dir.join("token").to_string_lossy().into_owned()),
+            ..Default::default()
+        };
+
+        Ok(ClusterContext {
+            name: "in-cluster".to_owned(),
+            cluster,
+            user,
+            namespace: Some(namespace.trim().to_owned()),
+            extensions: None,
+            cluster_name: "in-cluster".to_owned(),
+            user_name: "in-cluster".to_owned(),
+            config_paths: Vec::new(),
+        })
+    }
 }
 
 impl KubeConfig {
+    /// Creates an empty kubeconfig
+    pub fn new() -> KubeConfig {
+        KubeConfig {
+            kind: Some("Config".to_owned()),
+            api_version: Some("v1".to_owned()),
+            preferences: Some(Preferences::default()),
+            ..Default::default()
+        }
+    }
+
     pub fn load<P: AsRef<Path>>(path: P) -> Result<KubeConfig> {
         let f = File::open(path.as_ref()).chain_err(|| "Unable to open kubeconfig file")?;
-        serde_yaml::from_reader(f).chain_err(|| "Unable to parse kubeconfig file")
+        let mut config: KubeConfig = serde_yaml::from_reader(f).chain_err(|| "Unable to parse kubeconfig file")?;
+
+        let base_dir = path.as_ref().parent().map(Path::to_owned);
+        for cluster in &mut config.clusters {
+            cluster.cluster.base_dir = base_dir.clone();
+        }
+        for user in &mut config.users {
+            user.user.base_dir = base_dir.clone();
+        }
+        config.paths = vec![path.as_ref().to_owned()];
+        Ok(config)
+    }
+
+    /// Loads and merges the kubeconfig files listed in `$KUBECONFIG`
+    ///
+    /// `$KUBECONFIG` is a list of paths separated the same way as `$PATH`
+    /// (`:` on unix, `;` on windows). Files are merged with the same rules as `kubectl`:
+    /// the first file to set `current-context` wins, and clusters, users, and contexts
+    /// are merged by name with the first definition of a name winning.
+    /// Listed files that do not exist are skipped.
+    ///
+    /// If `$KUBECONFIG` is unset or empty, this loads `KubeConfig::default_path()`.
+    pub fn load_merged() -> Result<KubeConfig> {
+        let paths: Vec<PathBuf> = match env::var_os("KUBECONFIG") {
+            Some(ref var) if !var.is_empty() => {
+                env::split_paths(var)
+                    .filter(|p| !p.as_os_str().is_empty())
+                    .collect()
+            }
+            _ => return KubeConfig::load(KubeConfig::default_path()),
+        };
+
+        let mut merged: Option<KubeConfig> = None;
+        for path in paths.iter().filter(|p| p.exists()) {
+            let config = KubeConfig::load(path)
+                .chain_err(|| format!("Unable to load kubeconfig {}", path.display()))?;
+            merged = match merged {
+                Some(mut merged) => {
+                    merged.merge(config);
+                    Some(merged)
+                }
+                None => Some(config),
+            };
+        }
+
+        match merged {
+            Some(merged) => Ok(merged),
+            None => bail!("None of the files in KUBECONFIG exist"),
+        }
+    }
+
+    /// Merges another kubeconfig into this one
+    ///
+    /// Values already set on `self` take precedence over values from `other`,
+    /// so merging a list of files in order gives the first file priority.
+    pub fn merge(&mut self, other: KubeConfig) {
+        self.paths.extend(other.paths);
+        if self.current_context.is_empty() {
+            self.current_context = other.current_context;
+        }
+        if self.kind.is_none() {
+            self.kind = other.kind;
+        }
+        if self.api_version.is_none() {
+            self.api_version = other.api_version;
+        }
+        if self.preferences.is_none() {
+            self.preferences = other.preferences;
+        }
+
+        for cluster in other.clusters {
+            if !self.clusters.iter().any(|c| c.name == cluster.name) {
+                self.clusters.push(cluster);
+            }
+        }
+        for user in other.users {
+            if !self.users.iter().any(|u| u.name == user.name) {
+                self.users.push(user);
+            }
+        }
+        for context in other.contexts {
+            if !self.contexts.iter().any(|c| c.name == context.name) {
+                self.contexts.push(context);
+            }
+        }
+        if let Some(others) = other.extensions {
+            let exts = self.extensions.get_or_insert_with(Vec::new);
+            for ext in others {
+                if !exts.iter().any(|e| e.name == ext.name) {
+                    exts.push(ext);
+                }
+            }
+        }
     }
 
     pub fn context(&self, name: &str) -> Result<ClusterContext> {
@@ -171,25 +603,28 @@ impl KubeConfig {
             .filter(|c| c.name == ctx.cluster)
             .collect();
         let clu = match clus.len() {
-            0 => bail!("unknown cluster {}", name),
+            0 => bail!("unknown cluster {} in context {}", ctx.cluster, name),
             1 => &clus[0].cluster,
-            _ => bail!("ambiguous cluster {}", name),
+            _ => bail!("ambiguous cluster {} in context {}", ctx.cluster, name),
         };
         let auths: Vec<&NamedAuthInfo> = self.users
             .iter()
             .filter(|c| c.name == ctx.user)
             .collect();
         let auth = match auths.len() {
-            0 => bail!("unknown auth-info {}", name),
+            0 => bail!("unknown auth-info {} in context {}", ctx.user, name),
             1 => &auths[0].user,
-            _ => bail!("ambiguous auth-info {}", name),
+            _ => bail!("ambiguous auth-info {} in context {}", ctx.user, name),
         };
         let rc = ClusterContext {
             name: name.to_string(),
             cluster: clu.clone(),
             user: auth.clone(),
             namespace: ctx.namespace.clone(),
-            extensions: None,
+            extensions: ctx.extensions.clone(),
+            cluster_name: ctx.cluster.clone(),
+            user_name: ctx.user.clone(),
+            config_paths: self.paths.clone(),
         };
         Ok(rc)
     }
@@ -199,6 +634,67 @@ impl KubeConfig {
         self.context(dname)
     }
 
+    /// Adds a cluster, replacing any existing cluster with the same name
+    ///
+    /// This is similar to `kubectl config set-cluster`.
+    pub fn set_cluster(&mut self, name: &str, mut cluster: Cluster) -> &mut KubeConfig {
+        cluster.base_dir = self.base_dir();
+        match self.clusters.iter_mut().find(|c| c.name == name) {
+            Some(existing) => existing.cluster = cluster,
+            None => self.clusters.push(NamedCluster { name: name.to_owned(), cluster, other: BTreeMap::new() }),
+        }
+        self
+    }
+
+    /// Adds a user, replacing any existing user with the same name
+    ///
+    /// This is similar to `kubectl config set-credentials`.
+    pub fn set_credentials(&mut self, name: &str, mut user: AuthInfo) -> &mut KubeConfig {
+        user.base_dir = self.base_dir();
+        match self.users.iter_mut().find(|u| u.name == name) {
+            Some(existing) => existing.user = user,
+            None => self.users.push(NamedAuthInfo { name: name.to_owned(), user, other: BTreeMap::new() }),
+        }
+        self
+    }
+
+    /// Adds a context, replacing any existing context with the same name
+    ///
+    /// This is similar to `kubectl config set-context`.
+    pub fn set_context(&mut self, name: &str, context: Context) -> &mut KubeConfig {
+        match self.contexts.iter_mut().find(|c| c.name == name) {
+            Some(existing) => existing.context = context,
+            None => self.contexts.push(NamedContext { name: name.to_owned(), context, other: BTreeMap::new() }),
+        }
+        self
+    }
+
+    /// Sets the `current-context`
+    ///
+    /// This is similar to `kubectl config use-context`,
+    /// and fails if no context has the given name.
+    pub fn use_context(&mut self, name: &str) -> Result<&mut KubeConfig> {
+        if !self.contexts.iter().any(|c| c.name == name) {
+            bail!("unknown context {}", name);
+        }
+        self.current_context = name.to_owned();
+        Ok(self)
+    }
+
+    /// Writes the kubeconfig to a file
+    ///
+    /// Relative paths are written as they were loaded, and any fields
+    /// that this crate doesn't model are preserved.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let f = File::create(path.as_ref()).chain_err(|| "Unable to write kubeconfig file")?;
+        serde_yaml::to_writer(f, self).chain_err(|| "Unable to serialize kubeconfig file")
+    }
+
+    // Entries added to a loaded config resolve relative paths like the loaded entries
+    fn base_dir(&self) -> Option<PathBuf> {
+        self.paths.first().and_then(|p| p.parent()).map(Path::to_owned)
+    }
+
     pub fn default_path() -> PathBuf {
         env::home_dir()
             .unwrap_or("/root".into())
@@ -206,3 +702,182 @@ impl KubeConfig {
             .join("config")
     }
 }
+
+/// Writes updated `auth-provider` config values back to the kubeconfig that defines `user`
+///
+/// The first file in `paths` that defines `user` is updated, matching the
+/// precedence used when merging kubeconfig files. The file is edited as plain
+/// YAML so that any fields this crate doesn't model are preserved.
+pub(crate) fn persist_auth_provider_config(paths: &[PathBuf], user: &str, updates: &BTreeMap<String, String>) -> Result<()> {
+    use serde_yaml::Value;
+
+    let key = |k: &str| Value::String(k.to_owned());
+    for path in paths {
+        let mut doc: Value = {
+            let f = File::open(path).chain_err(|| "Unable to open kubeconfig file")?;
+            serde_yaml::from_reader(f).chain_err(|| "Unable to parse kubeconfig file")?
+        };
+
+        let updated = {
+            let provider_config = doc.as_mapping_mut()
+                .and_then(|doc| doc.get_mut(&key("users")))
+                .and_then(Value::as_sequence_mut)
+                .and_then(|users| users.iter_mut().find(|u| u.get("name").and_then(Value::as_str) == Some(user)))
+                .and_then(Value::as_mapping_mut)
+                .and_then(|u| u.get_mut(&key("user")))
+                .and_then(Value::as_mapping_mut)
+                .and_then(|u| u.get_mut(&key("auth-provider")))
+                .and_then(Value::as_mapping_mut)
+                .and_then(|p| p.get_mut(&key("config")))
+                .and_then(Value::as_mapping_mut);
+            match provider_config {
+                Some(provider_config) => {
+                    for (k, v) in updates {
+                        provider_config.insert(key(k), key(v));
+                    }
+                    true
+                }
+                None => false,
+            }
+        };
+
+        if updated {
+            let f = File::create(path).chain_err(|| "Unable to write kubeconfig file")?;
+            serde_yaml::to_writer(f, &doc).chain_err(|| "Unable to serialize kubeconfig file")?;
+            return Ok(());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(yaml: &str) -> KubeConfig {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn merge_keeps_first_current_context() {
+        let mut config = parse("current-context: first");
+        config.merge(parse("current-context: second"));
+        assert_eq!(config.current_context, "first");
+
+        let mut config = parse("clusters: []");
+        config.merge(parse("current-context: second"));
+        assert_eq!(config.current_context, "second");
+    }
+
+    #[test]
+    fn merge_keeps_first_definition_of_a_name() {
+        let mut config = parse(r#"
+clusters:
+- name: prod
+  cluster: { server: "https://first:6443" }
+users:
+- name: admin
+  user: { token: first }
+contexts:
+- name: prod
+  context: { cluster: prod, user: admin }
+"#);
+        config.merge(parse(r#"
+clusters:
+- name: prod
+  cluster: { server: "https://second:6443" }
+- name: stage
+  cluster: { server: "https://stage:6443" }
+users:
+- name: admin
+  user: { token: second }
+contexts:
+- name: prod
+  context: { cluster: prod, user: other }
+"#));
+
+        let servers: Vec<_> = config.clusters.iter().map(|c| (c.name.as_str(), c.cluster.server.host_str().unwrap())).collect();
+        assert_eq!(servers, vec![("prod", "first"), ("stage", "stage")]);
+        assert_eq!(config.users.len(), 1);
+        assert_eq!(config.users[0].user.token.as_ref().unwrap(), "first");
+        assert_eq!(config.contexts.len(), 1);
+        assert_eq!(config.contexts[0].context.user, "admin");
+    }
+
+    #[test]
+    fn merge_merges_extensions_by_name() {
+        let mut config = parse(r#"
+extensions:
+- name: a
+  extension: first
+"#);
+        config.merge(parse(r#"
+extensions:
+- name: a
+  extension: second
+- name: b
+  extension: second
+"#));
+
+        let exts: Vec<_> = config.extensions.unwrap().into_iter()
+            .map(|e| (e.name, e.extension.as_str().unwrap().to_owned()))
+            .collect();
+        assert_eq!(exts, vec![("a".to_owned(), "first".to_owned()), ("b".to_owned(), "second".to_owned())]);
+    }
+
+    #[test]
+    fn save_preserves_server_and_unknown_fields() {
+        let yaml = r#"
+clusters:
+- name: prod
+  cluster: { server: "https://prod:6443", future-field: 1 }
+  wrapper-field: a
+users:
+- name: admin
+  user: { token: abc }
+  wrapper-field: b
+contexts:
+- name: prod
+  context: { cluster: prod, user: admin }
+  wrapper-field: c
+"#;
+        let saved = serde_yaml::to_string(&parse(yaml)).unwrap();
+        let config = parse(&saved);
+        assert_eq!(serde_yaml::to_string(&config).unwrap(), saved);
+
+        let value: serde_yaml::Value = serde_yaml::from_str(&saved).unwrap();
+        assert_eq!(value["clusters"][0]["cluster"]["server"].as_str(), Some("https://prod:6443"));
+        assert_eq!(value["clusters"][0]["cluster"]["future-field"].as_i64(), Some(1));
+        assert_eq!(value["clusters"][0]["wrapper-field"].as_str(), Some("a"));
+        assert_eq!(value["users"][0]["wrapper-field"].as_str(), Some("b"));
+        assert_eq!(value["contexts"][0]["wrapper-field"].as_str(), Some("c"));
+    }
+
+    #[test]
+    fn save_writes_changed_server() {
+        let mut config = parse("clusters: [{ name: prod, cluster: { server: \"https://prod:6443\" } }]");
+        config.clusters[0].cluster.server = Url::parse("https://other:6443").unwrap();
+        let value: serde_yaml::Value = serde_yaml::from_str(&serde_yaml::to_string(&config).unwrap()).unwrap();
+        assert_eq!(value["clusters"][0]["cluster"]["server"].as_str(), Some("https://other:6443/"));
+    }
+
+    #[test]
+    fn service_account_dir_can_be_overridden() {
+        let dir = env::temp_dir().join(format!("kubeclient-sa-{}", ::std::process::id()));
+        ::std::fs::create_dir_all(&dir).unwrap();
+        File::create(dir.join("namespace")).unwrap().write_all(b"kube-system\n").unwrap();
+        env::set_var("KUBERNETES_SERVICE_HOST", "fd00::1");
+        env::set_var("KUBERNETES_SERVICE_PORT", "443");
+
+        let context = ClusterContext::from_service_account(&dir).unwrap();
+        ::std::fs::remove_dir_all(&dir).unwrap();
+        env::remove_var("KUBERNETES_SERVICE_HOST");
+        env::remove_var("KUBERNETES_SERVICE_PORT");
+
+        assert_eq!(context.cluster.server.as_str(), "https://[fd00::1]/");
+        assert_eq!(context.namespace.as_ref().unwrap(), "kube-system");
+        assert_eq!(context.cluster.ca_file, Some(dir.join("ca.crt").to_string_lossy().into_owned()));
+        assert_eq!(context.user.token_file_path(), Some(dir.join("token")));
+        assert!(context.config_paths.is_empty());
+    }
+}
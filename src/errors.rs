@@ -1,4 +1,4 @@
-
+use resources::Status;
 
 error_chain! {
     foreign_links {
@@ -8,4 +8,78 @@ error_chain! {
         Url(::url::ParseError);
         Http(::reqwest::Error);
     }
+
+    errors {
+        /// The API server rejected the request with a `Status`
+        Api(status: Box<Status>) {
+            description("Kubernetes API error")
+            display("Kubernetes API error: {}", status.message)
+        }
+
+        /// The server responded with an error that isn't a Kubernetes `Status`
+        UnexpectedResponse(code: u16, content_type: String, body: String) {
+            description("Unexpected error response")
+            display("Unexpected error response (HTTP {}, content type '{}'): {}", code, content_type, body)
+        }
+    }
+}
+
+impl Error {
+    /// The `Status` returned by the API server, if this is an API error
+    pub fn status(&self) -> Option<&Status> {
+        match *self.kind() {
+            ErrorKind::Api(ref status) => Some(&**status),
+            _ => None,
+        }
+    }
+
+    /// The HTTP status code of an error response
+    pub fn status_code(&self) -> Option<u16> {
+        match *self.kind() {
+            ErrorKind::Api(ref status) => Some(status.code),
+            ErrorKind::UnexpectedResponse(code, _, _) => Some(code),
+            _ => None,
+        }
+    }
+
+    /// The resource does not exist (404 `NotFound`)
+    pub fn is_not_found(&self) -> bool {
+        self.is_api_error(404, "NotFound")
+    }
+
+    /// The resource already exists (409 `AlreadyExists`)
+    pub fn is_already_exists(&self) -> bool {
+        match self.status() {
+            Some(status) => status.reason == "AlreadyExists",
+            None => false,
+        }
+    }
+
+    /// The write conflicted with a concurrent change (409 `Conflict`)
+    pub fn is_conflict(&self) -> bool {
+        self.is_api_error(409, "Conflict")
+    }
+
+    /// The user is not allowed to perform the request (403 `Forbidden`)
+    pub fn is_forbidden(&self) -> bool {
+        self.is_api_error(403, "Forbidden")
+    }
+
+    /// The request lacked valid credentials (401 `Unauthorized`)
+    pub fn is_unauthorized(&self) -> bool {
+        self.is_api_error(401, "Unauthorized")
+    }
+
+    /// The resource failed validation (422 `Invalid`)
+    pub fn is_invalid(&self) -> bool {
+        self.is_api_error(422, "Invalid")
+    }
+
+    fn is_api_error(&self, code: u16, reason: &str) -> bool {
+        match self.status() {
+            Some(status) if status.reason.is_empty() => status.code == code,
+            Some(status) => status.reason == reason,
+            None => false,
+        }
+    }
 }
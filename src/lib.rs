@@ -28,19 +28,27 @@
 //! ```
 
 #[macro_use] extern crate error_chain;
+#[macro_use] extern crate log;
 #[macro_use] extern crate serde_derive;
+#[macro_use] extern crate serde_json;
 
 extern crate base64;
 extern crate chrono;
+#[cfg(feature = "async")]
+extern crate futures;
 extern crate headers_ext;
+extern crate http;
 extern crate openssl;
 extern crate k8s_openapi;
+#[cfg(feature = "derive")]
+extern crate kubeclient_derive;
+extern crate rand;
 extern crate reqwest;
 extern crate serde;
-extern crate serde_json;
 extern crate serde_yaml;
+#[cfg(feature = "async")]
+extern crate tokio_timer;
 extern crate url;
-extern crate url_serde;
 extern crate walkdir;
 
 pub mod errors;
@@ -50,9 +58,103 @@ pub mod resources;
 
 pub mod prelude {
     pub use clients::{Kubernetes, ReadClient, WriteClient, ListClient};
+    #[cfg(feature = "async")]
+    pub use clients::async_clients::{AsyncKubernetes, AsyncReadClient, AsyncWriteClient, AsyncListClient};
 }
 
-pub use clients::Kubernetes;
+pub use clients::{Kubernetes, KubernetesBuilder, Middleware, RetryPolicy, Transport};
+#[cfg(feature = "async")]
+pub use clients::async_clients::AsyncKubernetes;
 pub use config::KubeConfig;
+/// Derives `Resource` and `ListableResource` for a custom resource type
+///
+/// ## Examples
+///
+/// ```
+/// # #[macro_use] extern crate serde_derive;
+/// # extern crate k8s_openapi;
+/// # extern crate kubeclient;
+/// use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+/// use kubeclient::CustomResource;
+/// use kubeclient::resources::{Resource, Scope};
+///
+/// #[derive(Serialize, Deserialize, CustomResource)]
+/// #[kube(group = "stable.example.com", version = "v1", namespaced)]
+/// pub struct CronTab {
+///     pub metadata: ObjectMeta,
+///     pub spec: CronTabSpec,
+/// }
+///
+/// #[derive(Serialize, Deserialize)]
+/// pub struct CronTabSpec {
+///     pub schedule: String,
+/// }
+///
+/// #[derive(Serialize, Deserialize, CustomResource)]
+/// #[kube(group = "policy.example.com", version = "v1beta1", kind = "Policy", plural = "policies")]
+/// pub struct ClusterPolicy {
+///     pub metadata: ObjectMeta,
+/// }
+///
+/// # fn main() {
+/// assert_eq!(CronTab::api_version(), "stable.example.com/v1");
+/// assert_eq!(CronTab::kind(), "CronTab");
+/// assert_eq!(CronTab::plural(), "crontabs");
+/// assert_eq!(CronTab::scope(), Scope::Namespaced);
+/// assert_eq!(CronTab::default_namespace(), Some("default"));
+///
+/// assert_eq!(ClusterPolicy::api_version(), "policy.example.com/v1beta1");
+/// assert_eq!(ClusterPolicy::kind(), "Policy");
+/// assert_eq!(ClusterPolicy::plural(), "policies");
+/// assert_eq!(ClusterPolicy::scope(), Scope::Cluster);
+/// assert_eq!(ClusterPolicy::default_namespace(), None);
+/// # }
+/// ```
+///
+/// The `group` and `version` are required:
+///
+/// ```compile_fail
+/// # #[macro_use] extern crate serde_derive;
+/// # extern crate k8s_openapi;
+/// # extern crate kubeclient;
+/// # use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+/// # use kubeclient::CustomResource;
+/// #[derive(Serialize, Deserialize, CustomResource)]
+/// #[kube(version = "v1")]
+/// pub struct CronTab {
+///     pub metadata: ObjectMeta,
+/// }
+/// # fn main() {}
+/// ```
+///
+/// ```compile_fail
+/// # #[macro_use] extern crate serde_derive;
+/// # extern crate k8s_openapi;
+/// # extern crate kubeclient;
+/// # use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+/// # use kubeclient::CustomResource;
+/// #[derive(Serialize, Deserialize, CustomResource)]
+/// #[kube(group = "stable.example.com")]
+/// pub struct CronTab {
+///     pub metadata: ObjectMeta,
+/// }
+/// # fn main() {}
+/// ```
+///
+/// And so is a `metadata` field:
+///
+/// ```compile_fail
+/// # #[macro_use] extern crate serde_derive;
+/// # extern crate kubeclient;
+/// # use kubeclient::CustomResource;
+/// #[derive(Serialize, Deserialize, CustomResource)]
+/// #[kube(group = "stable.example.com", version = "v1")]
+/// pub struct CronTab {
+///     pub spec: String,
+/// }
+/// # fn main() {}
+/// ```
+#[cfg(feature = "derive")]
+pub use kubeclient_derive::CustomResource;
 pub use errors::Error;
 
@@ -1,27 +1,27 @@
 use super::*;
+use serde_json::{Map, Value};
 use std::collections::BTreeMap;
 use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
 
-pub(crate) static CONFIG_MAP_INFO: KindInfo = KindInfo {
-    plural: "configmaps",
-    default_namespace: Some("default"),
-    api: V1_API,
-};
-
 #[derive(Serialize, Deserialize, Debug)]
 pub struct ConfigMap {
     /// Data contains the configuration data. Each key must consist of alphanumeric characters, '-', '_' or '.'.
+    #[serde(default)]
     data: BTreeMap<String, String>,
 
     /// Standard object's metadata. More info: https://git.k8s.io/community/contributors/devel/api-conventions.md#metadata
     metadata: ObjectMeta,
+
+    /// Fields not modeled above, kept so they aren't lost when the resource is replaced
+    #[serde(flatten)]
+    other: Map<String, Value>,
 }
 
 impl ConfigMap {
     pub fn new(name: &str) -> ConfigMap {
         let data = BTreeMap::new();
         let metadata = ObjectMeta{ name: Some(name.to_owned()), ..Default::default() };
-        ConfigMap { data, metadata }
+        ConfigMap { data, metadata, other: Map::new() }
     }
 
     pub fn insert<K, V>(&mut self, name: K, data: V) -> &mut ConfigMap
@@ -46,5 +46,10 @@ impl ConfigMap {
 }
 
 impl Resource for ConfigMap {
-    fn kind() -> Kind { Kind::ConfigMap }
+    fn group() -> &'static str { "" }
+    fn version() -> &'static str { "v1" }
+    fn kind() -> &'static str { "ConfigMap" }
+    fn plural() -> &'static str { "configmaps" }
+    fn scope() -> Scope { Scope::Namespaced }
+    fn metadata(&self) -> &ObjectMeta { &self.metadata }
 }
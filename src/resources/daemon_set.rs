@@ -1,13 +1,8 @@
 use super::*;
+use serde_json::{Map, Value};
 use k8s_openapi::api::apps::v1beta2::{DaemonSetSpec, DaemonSetStatus};
 use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
 
-pub(crate) static DAEMON_SET_INFO: KindInfo = KindInfo {
-    plural: "daemonsets",
-    default_namespace: Some("default"),
-    api: V1_BETA_API,
-};
-
 #[derive(Serialize, Deserialize, Debug)]
 pub struct DaemonSet {
     /// The desired behavior of this daemon set. More info: https://git.k8s.io/community/contributors/devel/api-conventions.md#spec-and-status
@@ -19,6 +14,10 @@ pub struct DaemonSet {
     /// The current status of this daemon set. This data may be out of date by some window of time. Populated by the system. Read-only. More info: https://git.k8s.io/community/contributors/devel/api-conventions.md#spec-and-status
     #[serde(skip_serializing_if = "Option::is_none")]
     pub status: Option<DaemonSetStatus>,
+
+    /// Fields not modeled above, kept so they aren't lost when the resource is replaced
+    #[serde(flatten)]
+    other: Map<String, Value>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Default)]
@@ -30,12 +29,17 @@ impl DaemonSet {
     pub fn new(name: &str) -> DaemonSet {
         let spec = DaemonSetSpec::default();
         let metadata = ObjectMeta{ name: Some(name.to_owned()), ..Default::default() };
-        DaemonSet { spec, metadata, status: None }
+        DaemonSet { spec, metadata, status: None, other: Map::new() }
     }
 }
 
 impl Resource for DaemonSet {
-    fn kind() -> Kind { Kind::DaemonSet }
+    fn group() -> &'static str { "extensions" }
+    fn version() -> &'static str { "v1beta1" }
+    fn kind() -> &'static str { "DaemonSet" }
+    fn plural() -> &'static str { "daemonsets" }
+    fn scope() -> Scope { Scope::Namespaced }
+    fn metadata(&self) -> &ObjectMeta { &self.metadata }
 }
 
 impl ListableResource for DaemonSet {
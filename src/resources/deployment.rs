@@ -1,14 +1,9 @@
 use super::*;
+use serde_json::{Map, Value};
 use k8s_openapi::api::apps::v1::{DeploymentSpec, DeploymentStatus};
 use k8s_openapi::api::apps::v1beta1::{ScaleSpec, ScaleStatus};
 use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
 
-pub(crate) static DEPLOYMENT_INFO: KindInfo = KindInfo {
-    plural: "deployments",
-    default_namespace: Some("default"),
-    api: V1_BETA_API,
-};
-
 #[derive(Serialize, Deserialize, Debug, Default)]
 pub struct Deployment {
     /// Specification of the desired behavior of the Deployment.
@@ -19,6 +14,10 @@ pub struct Deployment {
 
     /// Most recently observed status of the Deployment.
     pub status: Option<DeploymentStatus>,
+
+    /// Fields not modeled above, kept so they aren't lost when the resource is replaced
+    #[serde(flatten)]
+    other: Map<String, Value>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Default)]
@@ -62,7 +61,12 @@ impl Deployment {
 }
 
 impl Resource for Deployment {
-    fn kind() -> Kind { Kind::Deployment }
+    fn group() -> &'static str { "extensions" }
+    fn version() -> &'static str { "v1beta1" }
+    fn kind() -> &'static str { "Deployment" }
+    fn plural() -> &'static str { "deployments" }
+    fn scope() -> Scope { Scope::Namespaced }
+    fn metadata(&self) -> &ObjectMeta { &self.metadata }
 }
 
 impl ListableResource for Deployment {
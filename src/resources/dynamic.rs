@@ -0,0 +1,120 @@
+use super::*;
+use serde_json::{Map, Value};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
+
+/// Identifies a kind of resource by its API group, version, and plural name
+///
+/// ## Examples
+///
+/// ```
+/// # use kubeclient::resources::ApiResource;
+/// let crontabs = ApiResource {
+///     group: "stable.example.com".to_owned(),
+///     version: "v1".to_owned(),
+///     plural: "crontabs".to_owned(),
+///     namespaced: true,
+/// };
+/// assert_eq!(crontabs.api_version(), "stable.example.com/v1");
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ApiResource {
+    /// API group, which is empty for the core group (e.g. `Pods`)
+    pub group: String,
+    pub version: String,
+    /// Lowercase plural name used in URLs, e.g. `deployments`
+    pub plural: String,
+    /// Whether resources of this kind live in a namespace
+    pub namespaced: bool,
+}
+
+impl ApiResource {
+    /// The `apiVersion` of resources of this kind, e.g. `apps/v1` or `v1`
+    pub fn api_version(&self) -> String {
+        if self.group.is_empty() {
+            self.version.clone()
+        } else {
+            format!("{}/{}", self.group, self.version)
+        }
+    }
+
+    /// The path of this kind's API group version, e.g. `/apis/apps/v1` or `/api/v1`
+    pub(crate) fn api_path(&self) -> String {
+        if self.group.is_empty() {
+            format!("/api/{}", self.version)
+        } else {
+            format!("/apis/{}/{}", self.group, self.version)
+        }
+    }
+}
+
+/// A resource of any kind, with its fields other than `metadata` left as JSON
+///
+/// ## Examples
+///
+/// ```
+/// # #[macro_use] extern crate serde_json;
+/// # extern crate kubeclient;
+/// # use kubeclient::resources::DynamicObject;
+/// let mut crontab = DynamicObject::new("stable.example.com/v1", "CronTab", "my-crontab");
+/// crontab.data.insert("spec".to_owned(), json!({ "cronSpec": "* * * * */5" }));
+/// ```
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct DynamicObject {
+    #[serde(default)]
+    pub api_version: String,
+    #[serde(default)]
+    pub kind: String,
+    #[serde(default)]
+    pub metadata: ObjectMeta,
+    /// All other fields, e.g. `spec` and `status`
+    #[serde(flatten)]
+    pub data: Map<String, Value>,
+}
+
+impl DynamicObject {
+    pub fn new(api_version: &str, kind: &str, name: &str) -> DynamicObject {
+        let metadata = ObjectMeta { name: Some(name.to_owned()), ..Default::default() };
+        DynamicObject {
+            api_version: api_version.to_owned(),
+            kind: kind.to_owned(),
+            metadata,
+            data: Map::new(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DynamicObjectList {
+    #[serde(default)]
+    api_version: String,
+    #[serde(default)]
+    kind: String,
+    items: Vec<DynamicObject>,
+}
+
+impl DynamicObjectList {
+    /// The list's items, filling in the `apiVersion` and `kind` that
+    /// the API server omits from items
+    pub(crate) fn into_items(self) -> Vec<DynamicObject> {
+        let (api_version, list_kind) = (self.api_version, self.kind);
+        // Only strip one suffix, e.g. `ListList` is a list of `List`s
+        let kind = if list_kind.ends_with("List") {
+            &list_kind[..list_kind.len() - 4]
+        } else {
+            &list_kind[..]
+        };
+        self.items.into_iter()
+            .map(|mut item| {
+                if item.api_version.is_empty() {
+                    item.api_version = api_version.clone();
+                }
+                if item.kind.is_empty() {
+                    item.kind = kind.to_owned();
+                }
+                item
+            })
+            .collect()
+    }
+}
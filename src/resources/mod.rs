@@ -6,6 +6,7 @@ mod deployment;
 mod network_policy;
 mod pod;
 mod service;
+mod dynamic;
 
 pub use self::secret::*;
 pub use self::config_map::*;
@@ -15,57 +16,74 @@ pub use self::deployment::*;
 pub use self::network_policy::*;
 pub use self::pod::*;
 pub use self::service::*;
+pub use self::dynamic::*;
 
 use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
 use serde::Serialize;
 use serde::de::DeserializeOwned;
-use std::fmt;
+use serde_json::Value;
 use std::collections::BTreeMap;
-use std::ops::Deref;
 
-pub(crate) const V1_API: &str = "/api/v1";
-pub(crate) const V1_BETA_API: &str = "/apis/extensions/v1beta1";
+/// Whether resources of a kind live in a namespace or are cluster-wide
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Scope {
+    /// Resources live in a namespace, like `Pods`
+    Namespaced,
+    /// Resources are cluster-wide, like `Nodes`
+    Cluster,
+}
 
-#[derive(Serialize, Deserialize, Debug)]
-pub enum Kind { DaemonSet, Deployment, ConfigMap, NetworkPolicy, Node, Pod, Secret, Service }
+/// A kind of resource served by the Kubernetes API
+///
+/// Custom resources can derive this with `#[derive(CustomResource)]` when
+/// the `derive` feature is enabled, which also derives `ListableResource`.
+///
+/// ## Examples
+///
+/// ```
+/// # use kubeclient::resources::{Deployment, Resource};
+/// assert_eq!(Deployment::api_version(), "extensions/v1beta1");
+/// assert_eq!(Deployment::plural(), "deployments");
+/// ```
+pub trait Resource: Serialize + DeserializeOwned {
+    /// API group, which is empty for the core group (e.g. `Pods`)
+    fn group() -> &'static str;
+    fn version() -> &'static str;
+    /// The kind, e.g. `Deployment`
+    fn kind() -> &'static str;
+    /// Lowercase plural name used in URLs, e.g. `deployments`
+    fn plural() -> &'static str;
+    fn scope() -> Scope;
+    /// The resource's standard object metadata, e.g. its name and `resourceVersion`
+    fn metadata(&self) -> &ObjectMeta;
 
-impl Deref for Kind {
-    type Target = KindInfo;
-    fn deref(&self) -> &KindInfo {
-        match *self {
-            Kind::ConfigMap => &CONFIG_MAP_INFO,
-            Kind::DaemonSet => &DAEMON_SET_INFO,
-            Kind::Deployment => &DEPLOYMENT_INFO,
-            Kind::NetworkPolicy => &NETWORK_POLICY_INFO,
-            Kind::Node => &NODE_INFO,
-            Kind::Pod => &POD_INFO,
-            Kind::Secret => &SECRET_INFO,
-            Kind::Service => &SERVICE_INFO,
-        }
+    /// The `apiVersion` of this kind, e.g. `apps/v1` or `v1`
+    fn api_version() -> String {
+        Self::api_resource().api_version()
     }
-}
-
-#[derive(Copy, Clone, Debug)]
-pub struct KindInfo {
-    pub plural: &'static str,
-    pub default_namespace: Option<&'static str>,
-    pub api: &'static str,
-}
 
-// Debug output of Kind is exactly what we want for Display
-impl fmt::Display for Kind {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        fmt::Debug::fmt(self, f)
+    /// The path of this kind's API group version, e.g. `/apis/apps/v1` or `/api/v1`
+    fn api() -> String {
+        Self::api_resource().api_path()
     }
-}
 
-pub trait Resource: Serialize + DeserializeOwned {
-    fn kind() -> Kind;
-    fn api() -> &'static str {
-        Self::kind().api
+    /// Where this kind is served, e.g. for use with a `DynamicClient`
+    fn api_resource() -> ApiResource {
+        ApiResource {
+            group: Self::group().to_owned(),
+            version: Self::version().to_owned(),
+            plural: Self::plural().to_owned(),
+            namespaced: Self::scope() == Scope::Namespaced,
+        }
     }
+
+    /// The namespace used when a client doesn't specify one,
+    /// which is `None` for cluster-wide kinds
     fn default_namespace() -> Option<&'static str> {
-        Self::kind().default_namespace
+        match Self::scope() {
+            Scope::Namespaced => Some("default"),
+            Scope::Cluster => None,
+        }
     }
 }
 
@@ -74,14 +92,59 @@ pub trait ListableResource: Resource {
     fn list_items(response: Self::ListResponse) -> Vec<Self>;
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+/// The `Status` returned by the API server when a request fails
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
 #[serde(rename_all = "camelCase")]
 pub struct Status {
+    #[serde(default)]
     pub kind: String,
+    #[serde(default)]
     pub api_version: String,
+    #[serde(default)]
     pub metadata: ObjectMeta,
+    /// Either `Success` or `Failure`
+    #[serde(default)]
     pub status: String,
+    #[serde(default)]
     pub message: String,
+    /// Machine-readable reason for the failure, e.g. `NotFound` or `Conflict`
+    #[serde(default)]
+    pub reason: String,
+    /// HTTP status code of the response
+    #[serde(default)]
+    pub code: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<StatusDetails>,
+}
+
+/// Extended data about a failure, like the resource it applied to
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusDetails {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uid: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub causes: Vec<StatusCause>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub retry_after_seconds: Option<u32>,
+}
+
+/// A single cause of a failure, e.g. an invalid field
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusCause {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
 }
 
 #[derive(Clone, Debug, Default)]
@@ -132,3 +195,48 @@ impl ListQuery {
         new
     }
 }
+
+/// A change to make to a resource with `WriteClient::patch`
+///
+/// ## Examples
+///
+/// ```
+/// # #[macro_use] extern crate serde_json;
+/// # extern crate kubeclient;
+/// # use kubeclient::resources::Patch;
+/// let scale = Patch::Merge(json!({ "spec": { "replicas": 3 } }));
+/// let image = Patch::Json(json!([
+///     { "op": "replace", "path": "/spec/template/spec/containers/0/image", "value": "nginx:1.15" }
+/// ]));
+/// ```
+#[derive(Clone, Debug)]
+pub enum Patch {
+    /// A JSON merge patch (RFC 7386), where fields in the patch replace
+    /// those of the resource, and `null` fields are removed
+    Merge(Value),
+    /// A strategic merge patch, which is like a merge patch, except lists
+    /// like a pod's containers are merged by key instead of replaced
+    ///
+    /// Custom resources don't support strategic merge patches.
+    Strategic(Value),
+    /// A JSON patch (RFC 6902), which is an array of operations
+    /// like `{"op": "add", "path": "/metadata/labels/app", "value": "web"}`
+    Json(Value),
+}
+
+impl Patch {
+    /// The `Content-Type` the API server expects for this kind of patch
+    pub fn content_type(&self) -> &'static str {
+        match *self {
+            Patch::Merge(_) => "application/merge-patch+json",
+            Patch::Strategic(_) => "application/strategic-merge-patch+json",
+            Patch::Json(_) => "application/json-patch+json",
+        }
+    }
+
+    pub(crate) fn body(&self) -> &Value {
+        match *self {
+            Patch::Merge(ref body) | Patch::Strategic(ref body) | Patch::Json(ref body) => body,
+        }
+    }
+}
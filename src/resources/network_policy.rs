@@ -1,13 +1,8 @@
 use super::*;
+use serde_json::{Map, Value};
 use k8s_openapi::api::extensions::v1beta1::NetworkPolicySpec;
 use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
 
-pub(crate) static NETWORK_POLICY_INFO: KindInfo = KindInfo {
-    plural: "networkpolicies",
-    default_namespace: Some("default"),
-    api: V1_BETA_API,
-};
-
 #[derive(Serialize, Deserialize, Default, Debug)]
 pub struct NetworkPolicy {
     /// Specification of the desired behavior for this NetworkPolicy.
@@ -15,6 +10,10 @@ pub struct NetworkPolicy {
 
     /// Standard object's metadata. More info: https://git.k8s.io/community/contributors/devel/api-conventions.md#metadata
     pub metadata: ObjectMeta,
+
+    /// Fields not modeled above, kept so they aren't lost when the resource is replaced
+    #[serde(flatten)]
+    other: Map<String, Value>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Default)]
@@ -31,7 +30,12 @@ impl NetworkPolicy {
 }
 
 impl Resource for NetworkPolicy {
-    fn kind() -> Kind { Kind::NetworkPolicy }
+    fn group() -> &'static str { "extensions" }
+    fn version() -> &'static str { "v1beta1" }
+    fn kind() -> &'static str { "NetworkPolicy" }
+    fn plural() -> &'static str { "networkpolicies" }
+    fn scope() -> Scope { Scope::Namespaced }
+    fn metadata(&self) -> &ObjectMeta { &self.metadata }
 }
 
 
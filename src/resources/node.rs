@@ -1,13 +1,8 @@
 use super::*;
+use serde_json::{Map, Value};
 use k8s_openapi::api::core::v1::{NodeSpec, NodeStatus};
 use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
 
-pub(crate) static NODE_INFO: KindInfo = KindInfo {
-    plural: "nodes",
-    default_namespace: None,
-    api: V1_API,
-};
-
 #[derive(Serialize, Deserialize, Default, Debug)]
 pub struct Node {
     /// Spec defines the behavior of a node. https://git.k8s.io/community/contributors/devel/api-conventions.md#spec-and-status
@@ -20,6 +15,10 @@ pub struct Node {
     /// More info: https://git.k8s.io/community/contributors/devel/api-conventions.md#spec-and-status
     #[serde(skip_serializing_if = "Option::is_none")]
     pub status: Option<NodeStatus>,
+
+    /// Fields not modeled above, kept so they aren't lost when the resource is replaced
+    #[serde(flatten)]
+    other: Map<String, Value>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Default)]
@@ -35,7 +34,12 @@ impl Node {
 }
 
 impl Resource for Node {
-    fn kind() -> Kind { Kind::Node }
+    fn group() -> &'static str { "" }
+    fn version() -> &'static str { "v1" }
+    fn kind() -> &'static str { "Node" }
+    fn plural() -> &'static str { "nodes" }
+    fn scope() -> Scope { Scope::Cluster }
+    fn metadata(&self) -> &ObjectMeta { &self.metadata }
 }
 
 impl ListableResource for Node {
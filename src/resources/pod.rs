@@ -1,13 +1,8 @@
 use super::*;
+use serde_json::{Map, Value};
 use k8s_openapi::api::core::v1::{PodSpec, PodStatus};
 use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
 
-pub(crate) static POD_INFO: KindInfo = KindInfo {
-    plural: "pods",
-    default_namespace: Some("default"),
-    api: V1_API,
-};
-
 #[derive(Serialize, Deserialize, Debug, Default)]
 pub struct Pod {
     /// Specification of the desired behavior of the pod. More info: https://git.k8s.io/community/contributors/devel/api-conventions.md#spec-and-status
@@ -19,6 +14,10 @@ pub struct Pod {
     /// Most recently observed status of the pod. This data may not be up to date. Populated by the system. Read-only.
     /// More info: https://git.k8s.io/community/contributors/devel/api-conventions.md#spec-and-status
     pub status: Option<PodStatus>,
+
+    /// Fields not modeled above, kept so they aren't lost when the resource is replaced
+    #[serde(flatten)]
+    other: Map<String, Value>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Default)]
@@ -76,7 +75,12 @@ impl Pod {
 }
 
 impl Resource for Pod {
-    fn kind() -> Kind { Kind::Pod }
+    fn group() -> &'static str { "" }
+    fn version() -> &'static str { "v1" }
+    fn kind() -> &'static str { "Pod" }
+    fn plural() -> &'static str { "pods" }
+    fn scope() -> Scope { Scope::Namespaced }
+    fn metadata(&self) -> &ObjectMeta { &self.metadata }
 }
 
 
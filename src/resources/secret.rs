@@ -1,25 +1,26 @@
 use super::*;
+use serde_json::{Map, Value};
 use std::collections::BTreeMap;
 use base64;
 use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
 
-pub(crate) static SECRET_INFO: KindInfo = KindInfo {
-    plural: "secrets",
-    default_namespace: Some("default"),
-    api: V1_API,
-};
-
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Secret {
+    #[serde(default)]
     data: BTreeMap<String, String>,
     metadata: ObjectMeta,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    secret_type: Option<String>,
+    /// Fields not modeled above, kept so they aren't lost when the resource is replaced
+    #[serde(flatten)]
+    other: Map<String, Value>,
 }
 
 impl Secret {
     pub fn new(name: &str) -> Secret {
         let data = BTreeMap::new();
         let metadata = ObjectMeta{ name: Some(name.to_owned()), ..Default::default() };
-        Secret { data, metadata }
+        Secret { data, metadata, secret_type: None, other: Map::new() }
     }
 
     pub fn insert<K,V>(&mut self, name: K, secret: V) -> &mut Secret
@@ -42,6 +43,16 @@ impl Secret {
         self
     }
 
+    /// The `type` of the secret, e.g. `kubernetes.io/tls`, which the server defaults to `Opaque`
+    pub fn secret_type(&self) -> Option<&str> {
+        self.secret_type.as_deref()
+    }
+
+    pub fn set_secret_type(&mut self, secret_type: &str) -> &mut Secret {
+        self.secret_type = Some(secret_type.to_owned());
+        self
+    }
+
     pub fn get<K>(&self, name: K) -> Option<Vec<u8>>
     where K: AsRef<str>
     {
@@ -51,5 +62,10 @@ impl Secret {
 }
 
 impl Resource for Secret {
-    fn kind() -> Kind { Kind::Secret }
+    fn group() -> &'static str { "" }
+    fn version() -> &'static str { "v1" }
+    fn kind() -> &'static str { "Secret" }
+    fn plural() -> &'static str { "secrets" }
+    fn scope() -> Scope { Scope::Namespaced }
+    fn metadata(&self) -> &ObjectMeta { &self.metadata }
 }
@@ -1,13 +1,8 @@
 use super::*;
+use serde_json::{Map, Value};
 use k8s_openapi::apimachinery::pkg::apis::meta::v1::ObjectMeta;
 use k8s_openapi::api::core::v1::{ServiceSpec, ServiceStatus};
 
-pub(crate) static SERVICE_INFO: KindInfo = KindInfo {
-    plural: "services",
-    default_namespace: Some("default"),
-    api: V1_API,
-};
-
 #[derive(Serialize, Deserialize, Debug, Default)]
 pub struct Service {
     /// Spec defines the behavior of a service. https://git.k8s.io/community/contributors/devel/api-conventions.md#spec-and-status
@@ -19,6 +14,10 @@ pub struct Service {
     /// Most recently observed status of the service. Populated by the system. Read-only. More info: https://git.k8s.io/community/contributors/devel/api-conventions.md#spec-and-status
     #[serde(skip_serializing_if = "Option::is_none")]
     pub status: Option<ServiceStatus>,
+
+    /// Fields not modeled above, kept so they aren't lost when the resource is replaced
+    #[serde(flatten)]
+    other: Map<String, Value>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Default)]
@@ -34,7 +33,12 @@ impl Service {
 }
 
 impl Resource for Service {
-    fn kind() -> Kind { Kind::Service }
+    fn group() -> &'static str { "" }
+    fn version() -> &'static str { "v1" }
+    fn kind() -> &'static str { "Service" }
+    fn plural() -> &'static str { "services" }
+    fn scope() -> Scope { Scope::Namespaced }
+    fn metadata(&self) -> &ObjectMeta { &self.metadata }
 }
 
 